@@ -0,0 +1,132 @@
+use std::fmt;
+
+use crate::counts;
+use crate::report::Report;
+
+#[derive(Debug, PartialEq)]
+pub struct Column {
+    pub position: usize,
+    pub ones: usize,
+    pub zeros: usize,
+    // share of readings with a 1
+    pub ratio: f64,
+    // in bits, 0 for a constant column up to 1 for an even split
+    pub entropy: f64,
+    pub constant: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Analytics {
+    pub columns: Vec<Column>,
+    // phi coefficient between each pair of positions, None if either is constant
+    pub correlation: Vec<Vec<Option<f64>>>,
+}
+
+// the same per position counts rates works from, plus pairwise counts for correlation
+pub fn analyse(report: &Report) -> Analytics {
+    let readings = report.total(&report.all());
+    let total = readings as f64;
+    let ones = counts(report);
+    let columns = ones
+        .iter()
+        .enumerate()
+        .map(|(position, ones)| {
+            let ratio = *ones as f64 / total;
+            Column {
+                position,
+                ones: *ones,
+                zeros: readings - ones,
+                ratio,
+                entropy: entropy(ratio),
+                constant: *ones == 0 || *ones == readings,
+            }
+        })
+        .collect();
+
+    let correlation = (0..report.width())
+        .map(|i| {
+            (0..report.width())
+                .map(|j| {
+                    let both = report.ones(j, report.column(i)) as f64;
+                    let (a, b) = (ones[i] as f64, ones[j] as f64);
+                    let spread = (a * (total - a) * b * (total - b)).sqrt();
+                    if spread == 0.0 {
+                        None
+                    } else {
+                        Some((total * both - a * b) / spread)
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    Analytics { columns, correlation }
+}
+
+fn entropy(p: f64) -> f64 {
+    [p, 1.0 - p]
+        .iter()
+        .filter(|p| **p > 0.0)
+        .map(|p| -p * p.log2())
+        .sum()
+}
+
+impl fmt::Display for Analytics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:>4} {:>10} {:>10} {:>6} {:>7}", "bit", "ones", "zeros", "ratio", "entropy")?;
+        for c in &self.columns {
+            let constant = if c.constant { "  constant" } else { "" };
+            writeln!(f, "{:>4} {:>10} {:>10} {:>6.3} {:>7.3}{}", c.position, c.ones, c.zeros, c.ratio, c.entropy, constant)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "correlation")?;
+        write!(f, "{:>4}", "")?;
+        for j in 0..self.correlation.len() {
+            write!(f, " {:>6}", j)?;
+        }
+        writeln!(f)?;
+        for (i, row) in self.correlation.iter().enumerate() {
+            write!(f, "{:>4}", i)?;
+            for phi in row {
+                match phi {
+                    Some(phi) => write!(f, " {:>6.2}", phi)?,
+                    None => write!(f, " {:>6}", "-")?,
+                }
+            }
+            writeln!(f)?;
+        }
+        let constant: Vec<_> = self.columns.iter().filter(|c| c.constant).map(|c| c.position.to_string()).collect();
+        if !constant.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "constant bits: {}", constant.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyse() {
+        // bit 0 stuck at 1, bit 2 set on one line only, bit 3 the opposite of bit 1
+        let report = vec![
+            String::from("1100"),
+            String::from("1001"),
+            String::from("1110"),
+            String::from("1001"),
+        ];
+        let report = Report::parse(&report).unwrap();
+        let analytics = analyse(&report);
+        let first = &analytics.columns[0];
+        assert_eq!((first.ones, first.zeros, first.constant), (4, 0, true));
+        assert_eq!(first.entropy, 0.0);
+        let second = &analytics.columns[1];
+        assert_eq!((second.ones, second.zeros, second.constant), (2, 2, false));
+        assert_eq!((second.ratio, second.entropy), (0.5, 1.0));
+        assert_eq!(analytics.correlation[0][1], None);
+        assert_eq!(analytics.correlation[1][3], Some(-1.0));
+        assert_eq!(analytics.correlation[1][1], Some(1.0));
+        assert!((analytics.columns[2].entropy - 0.8113).abs() < 1e-4);
+    }
+}
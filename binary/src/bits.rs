@@ -0,0 +1,244 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Mul;
+
+/*
+unsigned value of any width, stored as little endian 64 bit words. bit 0 is
+the least significant. width is only used for rendering and complements;
+equality and ordering look at the value alone.
+*/
+#[derive(Clone, Debug)]
+pub struct Bits {
+    width: usize,
+    words: Vec<u64>,
+}
+
+impl Bits {
+    pub fn zero(width: usize) -> Bits {
+        Bits { width, words: vec![0; width.div_ceil(64)] }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn set(&mut self, bit: usize) {
+        self.words[bit / 64] |= 1 << (bit % 64);
+    }
+
+    pub fn get(&self, bit: usize) -> bool {
+        self.words.get(bit / 64).is_some_and(|word| word & (1 << (bit % 64)) != 0)
+    }
+
+    // flips every bit within width
+    pub fn not(&self) -> Bits {
+        let mut not = Bits::zero(self.width);
+        for bit in 0..self.width {
+            if !self.get(bit) {
+                not.set(bit);
+            }
+        }
+        not
+    }
+
+    // value of digits in radix, most significant first
+    pub fn from_digits(digits: &[u8], radix: u32) -> Bits {
+        let per_digit = (u32::BITS - (radix - 1).leading_zeros()) as usize;
+        let mut value = Bits::zero(digits.len() * per_digit);
+        for digit in digits {
+            value.mul_add(radix as u64, *digit as u64);
+        }
+        value
+    }
+
+    // how far apart two values are, whichever is larger
+    pub fn abs_diff(&self, other: &Bits) -> Bits {
+        let (high, low) = if self >= other { (self, other) } else { (other, self) };
+        let mut difference = Bits::zero(self.width.max(other.width));
+        difference.words.resize(high.words.len().max(low.words.len()), 0);
+        let mut borrow = 0;
+        for (i, word) in difference.words.iter_mut().enumerate() {
+            let (a, b) = (high.words.get(i).copied().unwrap_or(0), low.words.get(i).copied().unwrap_or(0));
+            let (current, under) = a.overflowing_sub(b);
+            let (current, under_borrow) = current.overflowing_sub(borrow);
+            *word = current;
+            borrow = (under || under_borrow) as u64;
+        }
+        difference
+    }
+
+    // self * m + a, which has to fit in width
+    fn mul_add(&mut self, m: u64, a: u64) {
+        let mut carry = a as u128;
+        for word in self.words.iter_mut() {
+            let current = *word as u128 * m as u128 + carry;
+            *word = current as u64;
+            carry = current >> 64;
+        }
+    }
+
+    // words without the leading zero words
+    fn significant(&self) -> &[u64] {
+        let len = self.words.iter().rposition(|word| *word != 0).map_or(0, |i| i + 1);
+        &self.words[..len]
+    }
+
+    // divides in place, returning the remainder
+    fn div_small(&mut self, divisor: u64) -> u64 {
+        let mut remainder = 0u128;
+        for word in self.words.iter_mut().rev() {
+            let current = remainder << 64 | *word as u128;
+            *word = (current / divisor as u128) as u64;
+            remainder = current % divisor as u128;
+        }
+        remainder as u64
+    }
+}
+
+impl From<u64> for Bits {
+    fn from(value: u64) -> Bits {
+        Bits { width: 64, words: vec![value] }
+    }
+}
+
+impl PartialEq for Bits {
+    fn eq(&self, other: &Bits) -> bool {
+        self.significant() == other.significant()
+    }
+}
+
+impl Eq for Bits {}
+
+impl Hash for Bits {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.significant().hash(state);
+    }
+}
+
+impl Ord for Bits {
+    fn cmp(&self, other: &Bits) -> Ordering {
+        let (a, b) = (self.significant(), other.significant());
+        a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+    }
+}
+
+impl PartialOrd for Bits {
+    fn partial_cmp(&self, other: &Bits) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Mul for &Bits {
+    type Output = Bits;
+
+    fn mul(self, other: &Bits) -> Bits {
+        let mut product = Bits::zero(self.width + other.width);
+        product.words.resize(self.words.len() + other.words.len(), 0);
+        for (i, a) in self.words.iter().enumerate() {
+            let mut carry = 0u128;
+            for (j, b) in other.words.iter().enumerate() {
+                let current = *a as u128 * *b as u128 + product.words[i + j] as u128 + carry;
+                product.words[i + j] = current as u64;
+                carry = current >> 64;
+            }
+            product.words[i + other.words.len()] = carry as u64;
+        }
+        product
+    }
+}
+
+// decimal
+impl fmt::Display for Bits {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // peel off 19 digits at a time, the most that fit in a u64
+        const CHUNK: u64 = 10_000_000_000_000_000_000;
+        let mut value = self.clone();
+        let mut chunks = vec![];
+        loop {
+            chunks.push(value.div_small(CHUNK));
+            if value.significant().is_empty() {
+                break;
+            }
+        }
+        let mut digits = chunks.pop().unwrap().to_string();
+        for chunk in chunks.iter().rev() {
+            digits.push_str(&format!("{:019}", chunk));
+        }
+        f.pad_integral(true, "", &digits)
+    }
+}
+
+impl fmt::LowerHex for Bits {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = match self.significant().split_last() {
+            Some((last, rest)) => {
+                let mut digits = format!("{:x}", last);
+                for word in rest.iter().rev() {
+                    digits.push_str(&format!("{:016x}", word));
+                }
+                digits
+            }
+            None => String::from("0"),
+        };
+        f.pad_integral(true, "0x", &digits)
+    }
+}
+
+// zero padded to width, the way readings appear in the report
+impl fmt::Binary for Bits {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits: String = (0..self.width).rev().map(|bit| if self.get(bit) { '1' } else { '0' }).collect();
+        f.pad_integral(true, "0b", &digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_abs_diff() {
+        assert_eq!(Bits::from(230).abs_diff(&Bits::from(198)), Bits::from(32));
+        assert_eq!(Bits::from(198).abs_diff(&Bits::from(230)), Bits::from(32));
+        let mut wide = Bits::zero(128);
+        wide.set(64);
+        assert_eq!(wide.abs_diff(&Bits::from(1)), Bits::from(u64::MAX));
+    }
+
+    #[test]
+    fn test_wide() {
+        let mut value = Bits::zero(128);
+        value.set(127);
+        value.set(0);
+        assert_eq!(value.to_string(), "170141183460469231731687303715884105729");
+        assert_eq!(format!("{:x}", value), "80000000000000000000000000000001");
+        assert_eq!(format!("{:#x}", Bits::zero(70)), "0x0");
+        assert_eq!(value.not().to_string(), "170141183460469231731687303715884105726");
+    }
+
+    #[test]
+    fn test_mul() {
+        assert_eq!(&Bits::from(9) * &Bits::from(22), Bits::from(198));
+        let max = Bits::from(u64::MAX);
+        let square = &max * &max;
+        assert_eq!(square.to_string(), "340282366920938463426481119284349108225");
+        assert!(square > max);
+    }
+
+    #[test]
+    fn test_from_digits() {
+        assert_eq!(Bits::from_digits(&[1, 0, 1, 1, 0], 2), Bits::from(22));
+        assert_eq!(Bits::from_digits(&[15, 15], 16), Bits::from(255));
+        assert_eq!(Bits::from_digits(&[9; 20], 10).to_string(), "99999999999999999999");
+    }
+
+    #[test]
+    fn test_binary() {
+        let mut value = Bits::zero(5);
+        value.set(4);
+        value.set(1);
+        assert_eq!(format!("{:b}", value), "10010");
+        assert_eq!(value, Bits::from(18));
+    }
+}
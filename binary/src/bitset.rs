@@ -0,0 +1,134 @@
+use std::ops::Range;
+
+// fixed size set of indices packed 64 to a word
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitSet {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitSet {
+    pub fn new(len: usize) -> BitSet {
+        BitSet { words: vec![0; len.div_ceil(64)], len }
+    }
+
+    pub fn full(len: usize) -> BitSet {
+        let mut set = BitSet { words: vec![u64::MAX; len.div_ceil(64)], len };
+        set.trim();
+        set
+    }
+
+    // clear the bits past len in the last word
+    fn trim(&mut self) {
+        let used = self.len % 64;
+        if used != 0 {
+            if let Some(last) = self.words.last_mut() {
+                *last &= (1 << used) - 1;
+            }
+        }
+    }
+
+    pub fn insert(&mut self, i: usize) {
+        self.words[i / 64] |= 1 << (i % 64);
+    }
+
+    pub fn remove(&mut self, i: usize) {
+        self.words[i / 64] &= !(1 << (i % 64));
+    }
+
+    pub fn contains(&self, i: usize) -> bool {
+        self.words[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    pub fn count(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    // size of the intersection, without building it
+    pub fn count_and(&self, other: &BitSet) -> usize {
+        self.count_and_words(other, 0..self.words.len())
+    }
+
+    // count_and over a run of words, so it can be split between threads
+    pub fn count_and_words(&self, other: &BitSet, words: Range<usize>) -> usize {
+        self.words[words.clone()]
+            .iter()
+            .zip(&other.words[words])
+            .map(|(a, b)| (a & b).count_ones() as usize)
+            .sum()
+    }
+
+    // size of the intersection with both a and b over a run of words
+    pub fn count_and_both_words(&self, a: &BitSet, b: &BitSet, words: Range<usize>) -> usize {
+        self.words[words.clone()]
+            .iter()
+            .zip(&a.words[words.clone()])
+            .zip(&b.words[words])
+            .map(|((x, a), b)| (x & a & b).count_ones() as usize)
+            .sum()
+    }
+
+    pub fn word_count(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn intersect(&mut self, other: &BitSet) {
+        for (a, b) in self.words.iter_mut().zip(&other.words) {
+            *a &= b;
+        }
+    }
+
+    pub fn difference(&mut self, other: &BitSet) {
+        for (a, b) in self.words.iter_mut().zip(&other.words) {
+            *a &= !b;
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(i, word)| {
+            let mut word = *word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(i * 64 + bit)
+            })
+        })
+    }
+
+    pub fn first(&self) -> Option<usize> {
+        self.iter().next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bitset() {
+        let mut a = BitSet::full(70);
+        assert_eq!(a.count(), 70);
+        let mut b = BitSet::new(70);
+        b.insert(3);
+        b.insert(65);
+        b.insert(69);
+        assert!(b.contains(65) && !b.contains(64));
+        b.insert(64);
+        b.remove(64);
+        assert!(!b.contains(64));
+        assert_eq!(a.count_and(&b), 3);
+        let mut c = BitSet::new(70);
+        c.insert(65);
+        c.insert(4);
+        assert_eq!(a.count_and_both_words(&b, &c, 0..2), 1);
+        assert_eq!(a.count_and_both_words(&b, &c, 0..1), 0);
+        a.difference(&b);
+        assert_eq!(a.count(), 67);
+        a.intersect(&b);
+        assert_eq!(a.first(), None);
+        assert_eq!(b.iter().collect::<Vec<_>>(), vec![3, 65, 69]);
+    }
+}
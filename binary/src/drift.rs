@@ -0,0 +1,176 @@
+use std::fmt;
+
+use crate::bits::Bits;
+use crate::error::DiagnosticError;
+use crate::layout::Layout;
+use crate::report::Report;
+use crate::TiePolicy;
+
+// gamma and epsilon over size consecutive readings
+#[derive(Debug, PartialEq, Eq)]
+pub struct Window {
+    // lines counting from 1, both ends included
+    pub first: usize,
+    pub last: usize,
+    pub gamma: Bits,
+    pub eps: Bits,
+}
+
+// a position whose majority changed as line came into the window
+#[derive(Debug, PartialEq, Eq)]
+pub struct Flip {
+    pub line: usize,
+    pub position: usize,
+    // the new most common bit
+    pub to: u32,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Drift {
+    pub windows: Vec<Window>,
+    pub flips: Vec<Flip>,
+}
+
+/*
+slides a window of size readings down the report one line at a time,
+keeping the ones at each position as a running count: the line coming in
+is added and the one going out taken off, so each step costs one reading
+rather than a whole window. repeat counts weigh in the same as in rates.
+*/
+pub fn drift(report: &Report, size: usize, tie: TiePolicy, layout: &Layout) -> Result<Drift, DiagnosticError> {
+    if size == 0 || size > report.len() {
+        return Err(DiagnosticError::Window { size, len: report.len() });
+    }
+    let width = report.width();
+    // only which positions count matters here, but a mask that doesn't fit is still an error
+    layout.positions(width)?;
+    let mut ones = vec![0; width];
+    let mut total = 0;
+    // adds reading i to the counts, or takes it off again
+    let update = |i: usize, ones: &mut [usize], total: &mut usize, add: bool| {
+        let weight = report.weight(i);
+        for (position, count) in ones.iter_mut().enumerate() {
+            if report.column(position).contains(i) {
+                if add { *count += weight } else { *count -= weight }
+            }
+        }
+        if add { *total += weight } else { *total -= weight }
+    };
+
+    let mut drift = Drift { windows: vec![], flips: vec![] };
+    let mut previous: Option<Vec<u32>> = None;
+    for last in 0..report.len() {
+        update(last, &mut ones, &mut total, true);
+        if last >= size {
+            update(last - size, &mut ones, &mut total, false);
+        }
+        if last + 1 < size {
+            continue;
+        }
+        let mut majority = vec![0; width];
+        for position in (0..width).filter(|position| layout.includes(*position)) {
+            majority[position] = tie.majority(ones[position], total, position)?;
+        }
+        if let Some(previous) = &previous {
+            for position in (0..width).filter(|position| majority[*position] != previous[*position]) {
+                drift.flips.push(Flip { line: last + 1, position, to: majority[position] });
+            }
+        }
+        let mut gamma = Bits::zero(width);
+        let mut eps = Bits::zero(width);
+        for position in (0..width).filter(|position| layout.includes(*position)) {
+            if majority[position] == 1 {
+                gamma.set(width - 1 - position);
+            } else {
+                eps.set(width - 1 - position);
+            }
+        }
+        drift.windows.push(Window { first: last + 2 - size, last: last + 1, gamma, eps });
+        previous = Some(majority);
+    }
+    Ok(drift)
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for window in &self.windows {
+            writeln!(f, "lines {}-{}: gamma {:b} epsilon {:b}", window.first, window.last, window.gamma, window.eps)?;
+        }
+        if !self.flips.is_empty() {
+            writeln!(f)?;
+            for flip in &self.flips {
+                writeln!(f, "line {}: bit {} flips to {}", flip.line, flip.position, flip.to)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rates;
+
+    #[test]
+    fn test_whole_report() {
+        let lines = vec![
+            String::from("00100"),
+            String::from("11110"),
+            String::from("10110"),
+            String::from("10111"),
+            String::from("10101"),
+            String::from("01111"),
+            String::from("00111"),
+            String::from("11100"),
+            String::from("10000"),
+            String::from("11001"),
+            String::from("00010"),
+            String::from("01010"),
+        ];
+        let report = Report::parse(&lines).unwrap();
+        // one window over everything is just rates
+        let drift = drift(&report, 12, TiePolicy::PreferOne, &Layout::default()).unwrap();
+        let rates = rates(&report, TiePolicy::PreferOne).unwrap();
+        assert_eq!(drift.windows, vec![Window { first: 1, last: 12, gamma: rates.gamma, eps: rates.eps }]);
+        assert!(drift.flips.is_empty());
+        // every window agrees with rates over the same lines
+        let drift = super::drift(&report, 5, TiePolicy::PreferOne, &Layout::default()).unwrap();
+        assert_eq!(drift.windows.len(), 8);
+        for window in &drift.windows {
+            let lines = Report::parse(&lines[window.first - 1..window.last]).unwrap();
+            assert_eq!(window.gamma, crate::rates(&lines, TiePolicy::PreferOne).unwrap().gamma);
+        }
+    }
+
+    #[test]
+    fn test_flips() {
+        // the first bit wears from 1 to 0 part way through
+        let lines: Vec<String> = ["10", "11", "10", "11", "00", "01", "00", "01"].iter().map(|s| s.to_string()).collect();
+        let report = Report::parse(&lines).unwrap();
+        let drift = drift(&report, 3, TiePolicy::PreferOne, &Layout::default()).unwrap();
+        // windows 1-3 10, 2-4 11, 3-5 10, 4-6 01, 5-7 00, 6-8 01
+        let gammas: Vec<_> = drift.windows.iter().map(|window| format!("{:b}", window.gamma)).collect();
+        assert_eq!(gammas, vec!["10", "11", "10", "01", "00", "01"]);
+        assert_eq!(
+            drift.flips,
+            vec![
+                Flip { line: 4, position: 1, to: 1 },
+                Flip { line: 5, position: 1, to: 0 },
+                Flip { line: 6, position: 0, to: 0 },
+                Flip { line: 6, position: 1, to: 1 },
+                Flip { line: 7, position: 1, to: 0 },
+                Flip { line: 8, position: 1, to: 1 },
+            ]
+        );
+        // with the second bit masked out only the real drift is left
+        let layout = Layout { mask: Some("10".parse().unwrap()), ..Default::default() };
+        let masked = super::drift(&report, 3, TiePolicy::PreferOne, &layout).unwrap();
+        assert_eq!(masked.flips, vec![Flip { line: 6, position: 0, to: 0 }]);
+        assert_eq!(
+            super::drift(&report, 9, TiePolicy::PreferOne, &layout),
+            Err(DiagnosticError::Window { size: 9, len: 8 })
+        );
+        let short = Layout { mask: Some("1".parse().unwrap()), ..Default::default() };
+        assert!(matches!(super::drift(&report, 3, TiePolicy::PreferOne, &short), Err(DiagnosticError::Layout(_))));
+    }
+}
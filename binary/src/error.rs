@@ -0,0 +1,71 @@
+use std::fmt;
+
+// lines count from 1, positions from the left of a reading starting at 0
+#[derive(Debug, PartialEq, Eq)]
+pub enum DiagnosticError {
+    Io(String),
+    Empty,
+    InvalidChar { line: usize, position: usize, found: char },
+    // anything after a reading other than a repeat count like x42
+    InvalidWeight { line: usize, found: String },
+    // repeat counts adding up to more readings than a usize can count, as of line
+    Overflow { line: usize },
+    // position is the first bit the line is missing, or the first extra one
+    Ragged { line: usize, position: usize, width: usize },
+    // as many "1"s as "0"s under TiePolicy::Error
+    Tie { position: usize },
+    // the criterion kept a bit none of the candidates had
+    Exhausted { position: usize },
+    // count identical readings survived under Duplicates::Error, the first on line
+    Duplicate { line: usize, count: usize },
+    // the generator found no report meeting the targets, or there is none
+    Unreachable(String),
+    // a rolling window of no readings, or more than the report has
+    Window { size: usize, len: usize },
+    // an order or mask that doesn't fit the report
+    Layout(String),
+    // digits only go from 2 to 36, 0-9 then a-z
+    Radix(u32),
+    // a pattern to measure readings against that isn't as wide as they are
+    Pattern { width: usize, expected: usize },
+    // a command line flag missing its value, or with one that doesn't parse
+    Argument(String),
+}
+
+impl fmt::Display for DiagnosticError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiagnosticError::Io(message) => write!(f, "{}", message),
+            DiagnosticError::Empty => write!(f, "report is empty"),
+            DiagnosticError::InvalidChar { line, position, found } => {
+                write!(f, "line {}: invalid char {:?} at bit {}", line, found, position)
+            }
+            DiagnosticError::InvalidWeight { line, found } => {
+                write!(f, "line {}: invalid repeat count {:?}", line, found)
+            }
+            DiagnosticError::Overflow { line } => {
+                write!(f, "line {}: repeat counts add up to more than {} readings", line, usize::MAX)
+            }
+            DiagnosticError::Ragged { line, position, width } => {
+                write!(f, "line {}: ragged at bit {}, expected {} bits", line, position, width)
+            }
+            DiagnosticError::Tie { position } => write!(f, "bit {}: tied", position),
+            DiagnosticError::Exhausted { position } => write!(f, "bit {}: no readings left", position),
+            DiagnosticError::Duplicate { line, count } => {
+                write!(f, "line {}: rating matches {} identical readings", line, count)
+            }
+            DiagnosticError::Unreachable(reason) => write!(f, "cannot generate report: {}", reason),
+            DiagnosticError::Window { size, len } => {
+                write!(f, "window of {} readings doesn't fit a report of {}", size, len)
+            }
+            DiagnosticError::Layout(reason) => write!(f, "invalid layout: {}", reason),
+            DiagnosticError::Radix(radix) => write!(f, "radix {} is outside 2 to 36", radix),
+            DiagnosticError::Argument(message) => write!(f, "{}", message),
+            DiagnosticError::Pattern { width, expected } => {
+                write!(f, "pattern has {} bits, expected {} bits", width, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DiagnosticError {}
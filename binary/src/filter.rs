@@ -0,0 +1,298 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use crate::bits::Bits;
+use crate::bitset::BitSet;
+use crate::report::Report;
+use crate::trace::Step;
+use crate::error::DiagnosticError;
+use crate::layout::{self, Layout};
+use crate::TiePolicy;
+
+// ones and zeros at a position among the readings still in the running
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Counts {
+    pub position: usize,
+    pub ones: usize,
+    pub zeros: usize,
+}
+
+// which bit to keep at each position
+pub enum Criterion {
+    MostCommon(TiePolicy),
+    LeastCommon(TiePolicy),
+    // true keeps the readings with a 1
+    Custom(Box<dyn Fn(Counts) -> bool>),
+}
+
+impl Criterion {
+    pub fn oxygen() -> Criterion {
+        Criterion::MostCommon(TiePolicy::PreferOne)
+    }
+
+    pub fn c02() -> Criterion {
+        Criterion::LeastCommon(TiePolicy::PreferZero)
+    }
+
+    pub fn keep(&self, counts: Counts) -> Result<u32, DiagnosticError> {
+        match self {
+            Criterion::MostCommon(tie) => tie.majority(counts.ones, counts.ones + counts.zeros, counts.position),
+            Criterion::LeastCommon(tie) => match counts.ones.cmp(&counts.zeros) {
+                Ordering::Greater => Ok(0),
+                Ordering::Less => Ok(1),
+                Ordering::Equal => tie.settle(counts.position),
+            },
+            Criterion::Custom(keep) => Ok(keep(counts) as u32),
+        }
+    }
+}
+
+// what to do when the readings left are all the same
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Duplicates {
+    // one rating, as if the report only had it once
+    #[default]
+    Merge,
+    // one rating, with multiplicity saying how many readings it stands for
+    Count,
+    Error,
+}
+
+impl FromStr for Duplicates {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Duplicates, String> {
+        match s {
+            "merge" => Ok(Duplicates::Merge),
+            "count" => Ok(Duplicates::Count),
+            "error" => Ok(Duplicates::Error),
+            _ => Err(format!("invalid duplicates policy {}", s)),
+        }
+    }
+}
+
+impl Duplicates {
+    // line is where the first of the survivors is in the report
+    pub fn resolve(self, value: Bits, position: Option<usize>, count: usize, line: usize) -> Result<Rating, DiagnosticError> {
+        match self {
+            Duplicates::Error if count > 1 => Err(DiagnosticError::Duplicate { line, count }),
+            Duplicates::Count => Ok(Rating { value, position, multiplicity: count }),
+            _ => Ok(Rating { value, position, multiplicity: 1 }),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Options {
+    pub duplicates: Duplicates,
+    pub layout: Layout,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Rating {
+    pub value: Bits,
+    // last position that was filtered on, None if there only ever was one reading
+    pub position: Option<usize>,
+    pub multiplicity: usize,
+}
+
+/*
+keeps the readings matching criterion at each position until one is left.
+if the criterion asks for a bit none of the candidates have, that's only
+fine when they are all the same reading, which then goes to the duplicates
+policy the same as identical readings that last to the end.
+*/
+pub fn filter(report: &Report, criterion: &Criterion, options: &Options) -> Result<Rating, DiagnosticError> {
+    run(report, criterion, options, report.all(), None)
+}
+
+// filter over only some of the readings, as if the rest weren't in the report
+pub fn filter_among(report: &Report, criterion: &Criterion, options: &Options, candidates: BitSet) -> Result<Rating, DiagnosticError> {
+    run(report, criterion, options, candidates, None)
+}
+
+// filter, optionally noting down every step for trace
+pub(crate) fn run(
+    report: &Report,
+    criterion: &Criterion,
+    options: &Options,
+    mut candidates: BitSet,
+    mut trace: Option<&mut Vec<Step>>,
+) -> Result<Rating, DiagnosticError> {
+    let positions = options.layout.positions(report.width())?;
+    if report.total(&candidates) == 0 {
+        return Err(DiagnosticError::Empty);
+    }
+    let mut decided = None;
+    for (i, &position) in positions.iter().enumerate() {
+        if report.total(&candidates) <= 1 {
+            break;
+        }
+        let ones = report.ones(position, &candidates);
+        let counts = Counts { position, ones, zeros: report.total(&candidates) - ones };
+        let keep = criterion.keep(counts)?;
+        let kept = if keep == 1 { counts.ones } else { counts.zeros };
+        if kept == 0 && identical(report, &candidates, &positions[i..]) {
+            break;
+        }
+        let before = candidates.clone();
+        if keep == 1 {
+            candidates.intersect(report.column(position));
+        } else {
+            candidates.difference(report.column(position));
+        }
+        if let Some(steps) = trace.as_mut() {
+            let mut dropped = before;
+            dropped.difference(&candidates);
+            let dropped = dropped.iter().map(|i| i + 1).collect();
+            steps.push(Step { position, ones: counts.ones, zeros: counts.zeros, keep, kept, dropped });
+        }
+        if kept == 0 {
+            return Err(DiagnosticError::Exhausted { position });
+        }
+        decided = Some(position);
+    }
+    let first = candidates.first().unwrap();
+    let value = layout::keep(&report.reading(first), &positions);
+    options.duplicates.resolve(value, decided, report.total(&candidates), first + 1)
+}
+
+// whether every candidate has the same bits at the positions still to come
+fn identical(report: &Report, candidates: &BitSet, positions: &[usize]) -> bool {
+    let count = report.total(candidates);
+    positions.iter().all(|p| {
+        let ones = report.ones(*p, candidates);
+        ones == 0 || ones == count
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom() {
+        let report = vec![
+            String::from("00100"),
+            String::from("11110"),
+            String::from("10110"),
+            String::from("10111"),
+            String::from("10101"),
+            String::from("01111"),
+            String::from("00111"),
+            String::from("11100"),
+            String::from("10000"),
+            String::from("11001"),
+            String::from("00010"),
+            String::from("01010"),
+        ];
+        let report = Report::parse(&report).unwrap();
+        // keep 1 at even positions and 0 at odd ones while that leaves anything
+        let criterion = Criterion::Custom(Box::new(|counts: Counts| {
+            match (counts.position % 2, counts.ones, counts.zeros) {
+                (0, 0, _) => false,
+                (0, _, _) => true,
+                (_, _, 0) => true,
+                _ => false,
+            }
+        }));
+        let rating = filter(&report, &criterion, &Options::default()).unwrap();
+        // 1 leaves 7, 0 leaves 10110 10111 10101 10000, 1 leaves three, 0 leaves 10101
+        assert_eq!(rating, Rating { value: Bits::from(0b10101), position: Some(3), multiplicity: 1 });
+    }
+
+    #[test]
+    fn test_least_common_tie() {
+        let report = Report::parse(&[String::from("10"), String::from("01")]).unwrap();
+        let options = Options::default();
+        let zero = filter(&report, &Criterion::LeastCommon(TiePolicy::PreferZero), &options).unwrap();
+        assert_eq!(zero.value, Bits::from(0b01));
+        let one = filter(&report, &Criterion::LeastCommon(TiePolicy::PreferOne), &options).unwrap();
+        assert_eq!(one.value, Bits::from(0b10));
+        assert!(filter(&report, &Criterion::LeastCommon(TiePolicy::Error), &options).is_err());
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let report = vec![
+            String::from("101"),
+            String::from("010"),
+            String::from("101"),
+            String::from("010"),
+            String::from("101"),
+        ];
+        let report = Report::parse(&report).unwrap();
+        let merge = Options { duplicates: Duplicates::Merge, ..Default::default() };
+        let count = Options { duplicates: Duplicates::Count, ..Default::default() };
+        let error = Options { duplicates: Duplicates::Error, ..Default::default() };
+        // three 101s outvote two 010s, then nothing splits them
+        assert_eq!(
+            filter(&report, &Criterion::oxygen(), &merge).unwrap(),
+            Rating { value: Bits::from(0b101), position: Some(2), multiplicity: 1 }
+        );
+        assert_eq!(filter(&report, &Criterion::oxygen(), &count).unwrap().multiplicity, 3);
+        assert_eq!(
+            filter(&report, &Criterion::oxygen(), &error),
+            Err(DiagnosticError::Duplicate { line: 1, count: 3 })
+        );
+        // the two 010s both lack the least common bit at position 1
+        assert_eq!(
+            filter(&report, &Criterion::c02(), &count).unwrap(),
+            Rating { value: Bits::from(0b010), position: Some(0), multiplicity: 2 }
+        );
+        assert_eq!(
+            filter(&report, &Criterion::c02(), &error),
+            Err(DiagnosticError::Duplicate { line: 2, count: 2 })
+        );
+    }
+
+    #[test]
+    fn test_layout() {
+        let lines = vec![
+            String::from("00100"),
+            String::from("11110"),
+            String::from("10110"),
+            String::from("10111"),
+            String::from("10101"),
+            String::from("01111"),
+            String::from("00111"),
+            String::from("11100"),
+            String::from("10000"),
+            String::from("11001"),
+            String::from("00010"),
+            String::from("01010"),
+        ];
+        let report = Report::parse(&lines).unwrap();
+        let binary = |value: &Bits| format!("{:b}", value);
+        let reversed: Vec<String> = lines.iter().map(|line| line.chars().rev().collect()).collect();
+        let reversed = Report::parse(&reversed).unwrap();
+        let lsb = Options { layout: Layout { order: layout::Order::LsbFirst, mask: None }, ..Default::default() };
+        // lsb first goes the same way as msb first over the mirrored report
+        for criterion in [Criterion::oxygen(), Criterion::c02()] {
+            let rating = filter(&report, &criterion, &lsb).unwrap();
+            let mirrored = filter(&reversed, &criterion, &Options::default()).unwrap();
+            assert_eq!(binary(&rating.value), binary(&mirrored.value).chars().rev().collect::<String>());
+        }
+        // masking a position out is like the report never having it
+        let dropped: Vec<String> = lines.iter().map(|line| format!("{}{}", &line[..2], &line[3..])).collect();
+        let dropped = Report::parse(&dropped).unwrap();
+        let masked = Options { layout: Layout { order: Default::default(), mask: Some("11011".parse().unwrap()) }, ..Default::default() };
+        let rating = binary(&filter(&report, &Criterion::oxygen(), &masked).unwrap().value);
+        let without = binary(&filter(&dropped, &Criterion::oxygen(), &Options::default()).unwrap().value);
+        assert_eq!(rating, format!("{}0{}", &without[..2], &without[2..]));
+        // both run out at the same reading, one position along for the mask
+        assert_eq!(filter(&report, &Criterion::c02(), &masked), Err(DiagnosticError::Exhausted { position: 3 }));
+        assert_eq!(filter(&dropped, &Criterion::c02(), &Options::default()), Err(DiagnosticError::Exhausted { position: 2 }));
+    }
+
+    #[test]
+    fn test_all_repeats() {
+        let report = Report::parse(&vec![String::from("0110"); 50]).unwrap();
+        let count = Options { duplicates: Duplicates::Count, ..Default::default() };
+        for criterion in [Criterion::oxygen(), Criterion::c02()] {
+            let rating = filter(&report, &criterion, &count).unwrap();
+            assert_eq!(rating.value, Bits::from(0b0110));
+            assert_eq!(rating.multiplicity, 50);
+        }
+    }
+}
@@ -0,0 +1,426 @@
+use std::cmp::Ordering;
+
+use crate::bits::Bits;
+use crate::error::DiagnosticError;
+use crate::filter::{filter, Criterion, Options};
+use crate::report::Report;
+use crate::{rates, TiePolicy};
+
+// xorshift, seeded through a splitmix step so any seed, 0 included, works
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        Rng((z ^ (z >> 31)) | 1)
+    }
+
+    pub fn word(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.word() % n as u64) as usize
+    }
+}
+
+// readings of width with every bit a coin flip, repeats and all
+pub fn random(width: usize, len: usize, seed: u64) -> Vec<String> {
+    let mut rng = Rng::new(seed);
+    (0..len)
+        .map(|_| {
+            let mut word = 0;
+            (0..width)
+                .map(|position| {
+                    if position % 64 == 0 {
+                        word = rng.word();
+                    }
+                    if word >> (position % 64) & 1 == 1 { '1' } else { '0' }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// what the generated report should rate as, under the default tie policy
+pub struct Spec {
+    pub width: usize,
+    pub len: usize,
+    pub gamma: Option<Bits>,
+    // has to be the complement of gamma, it stands in for gamma when that is missing
+    pub epsilon: Option<Bits>,
+    pub oxygen: Bits,
+    pub c02: Bits,
+    // shuffles the readings, and fills the free bits when there is no gamma
+    pub seed: u64,
+}
+
+/*
+the readings split on the first bit into the oxygen side, the majority, and
+the c02 side. each side is a chain that follows its rating and drops as many
+readings at each position as the criterion allows, so every dropped reading
+is free from the bit after it drops. the free bits then go to gamma, and
+the finished report is run back through rates and filter to check it.
+*/
+pub fn generate(spec: &Spec) -> Result<Vec<String>, DiagnosticError> {
+    let width = spec.width;
+    let unreachable = |reason: &str| Err(DiagnosticError::Unreachable(String::from(reason)));
+    if width == 0 || spec.len == 0 {
+        return unreachable("width and length have to be at least 1");
+    }
+    let gamma = match (&spec.gamma, &spec.epsilon) {
+        (Some(gamma), Some(epsilon)) if gamma.not() != *epsilon || !fits(epsilon, width) => {
+            return unreachable("epsilon is not the complement of gamma");
+        }
+        (Some(gamma), _) => Some(gamma.clone()),
+        (None, Some(epsilon)) => Some(resize(epsilon, width).not()),
+        (None, None) => None,
+    };
+    for (name, value) in [("gamma", gamma.as_ref()), ("oxygen", Some(&spec.oxygen)), ("c02", Some(&spec.c02))] {
+        if value.is_some_and(|value| !fits(value, width)) {
+            return unreachable(&format!("{} does not fit in {} bits", name, width));
+        }
+    }
+    let oxygen = |position: usize| spec.oxygen.get(width - 1 - position);
+    let c02 = |position: usize| spec.c02.get(width - 1 - position);
+
+    // every reading the same is both ratings at once
+    if spec.oxygen == spec.c02 {
+        let row: Vec<Option<bool>> = (0..width).map(|p| Some(oxygen(p))).collect();
+        return settle(spec, gamma.as_ref(), vec![row; spec.len]);
+    }
+    if spec.len == 1 {
+        return unreachable("a single reading is both ratings");
+    }
+    if oxygen(0) == c02(0) {
+        return unreachable("oxygen and c02 have to differ in the first bit");
+    }
+    // an even split keeps the 1 for oxygen, so a 0 needs a strict majority
+    let most = if spec.len.is_multiple_of(2) && !oxygen(0) { spec.len / 2 - 1 } else { spec.len / 2 };
+    if most == 0 {
+        return unreachable("two readings split evenly, so oxygen has to start with 1");
+    }
+    // two left on the c02 side can never keep a 1, unless they are the same reading
+    let first = if most == 2 && width > 1 && c02(1) { 1 } else { most };
+
+    /*
+    the first try gives c02 as many readings as it can have and drops as many
+    as it can at each position. when that misses, every other size of the c02
+    side gets a go, and then all of them again with the c02 side free to stop
+    dropping and keep copies of its rating when gamma needs them.
+    */
+    let mut missed = None;
+    for hold in [false, true] {
+        for minority in std::iter::once(first).chain((1..=most).rev().filter(|minority| *minority != first)) {
+            let rows = chains(spec, gamma.as_ref(), [&oxygen, &c02], minority, hold);
+            match settle(spec, gamma.as_ref(), rows) {
+                Ok(lines) => return Ok(lines),
+                Err(e) => {
+                    missed.get_or_insert(e);
+                }
+            }
+        }
+    }
+    Err(missed.unwrap())
+}
+
+// both sides followed down to the end, with the rows they dropped on the way
+fn chains(
+    spec: &Spec,
+    gamma: Option<&Bits>,
+    [oxygen, c02]: [&dyn Fn(usize) -> bool; 2],
+    minority: usize,
+    hold: bool,
+) -> Vec<Vec<Option<bool>>> {
+    let width = spec.width;
+    let mut sides = [Side::new(oxygen, false, spec.len - minority), Side::new(c02, true, minority)];
+    for position in 1..width {
+        let mut keeps = sides.each_ref().map(|side| side.keep(position));
+        // lean on the sides only as far as gamma needs
+        if let Some(gamma) = gamma {
+            let bit = gamma.get(width - 1 - position);
+            let free = spec.len - sides.iter().map(|side| side.left).sum::<usize>();
+            let mut agree = free + (0..2).map(|i| sides[i].agree(position, keeps[i], bit)).sum::<usize>();
+            let needed = if bit { spec.len.div_ceil(2) } else { spec.len / 2 + 1 };
+            for i in 0..2 {
+                if agree >= needed || sides[i].left <= 1 {
+                    continue;
+                }
+                let (least, most) = sides[i].range(position);
+                let room = if sides[i].target(position) == bit { most - keeps[i] } else { keeps[i] - least };
+                let mut moved = room.min(needed - agree);
+                if sides[i].target(position) == bit {
+                    // identical readings end the filter, so the minority can keep them all
+                    if hold && sides[i].least && agree + room < needed {
+                        moved = sides[i].left - keeps[i];
+                    }
+                    keeps[i] += moved;
+                } else {
+                    keeps[i] -= moved;
+                }
+                agree += moved;
+            }
+        }
+        for (side, keep) in sides.iter_mut().zip(keeps) {
+            side.step(position, keep, width);
+        }
+    }
+    sides.into_iter().flat_map(|side| side.finish(width)).collect()
+}
+
+// fills in the free bits, shuffles, and checks the report rates as asked
+fn settle(spec: &Spec, gamma: Option<&Bits>, rows: Vec<Vec<Option<bool>>>) -> Result<Vec<String>, DiagnosticError> {
+    let width = spec.width;
+    let mut rng = Rng::new(spec.seed);
+    let mut lines: Vec<String> = rows
+        .iter()
+        .map(|row| {
+            (0..width)
+                .map(|position| {
+                    let bit = row[position].unwrap_or_else(|| match gamma {
+                        Some(gamma) => gamma.get(width - 1 - position),
+                        None => rng.word() & 1 == 1,
+                    });
+                    if bit { '1' } else { '0' }
+                })
+                .collect()
+        })
+        .collect();
+    for i in (1..lines.len()).rev() {
+        lines.swap(i, rng.below(i + 1));
+    }
+
+    let missed = |what: String| Err(DiagnosticError::Unreachable(format!("generator could not find one with {}", what)));
+    let report = Report::parse(&lines)?;
+    if let Some(gamma) = gamma {
+        if rates(&report, TiePolicy::PreferOne).ok().map(|rates| rates.gamma).as_ref() != Some(gamma) {
+            return missed(format!("gamma {:b} alongside these ratings", gamma));
+        }
+    }
+    let options = Options::default();
+    let rating = |criterion: &Criterion| filter(&report, criterion, &options).ok().map(|rating| rating.value);
+    if rating(&Criterion::oxygen()).as_ref() != Some(&spec.oxygen) {
+        return missed(format!("oxygen {:b}", spec.oxygen));
+    }
+    if rating(&Criterion::c02()).as_ref() != Some(&spec.c02) {
+        return missed(format!("c02 {:b}", spec.c02));
+    }
+    Ok(lines)
+}
+
+// readings on one side of the first bit, following target down as long as more than one is left
+struct Side<'a> {
+    target: &'a dyn Fn(usize) -> bool,
+    // least common keeps the minority
+    least: bool,
+    left: usize,
+    // the readings left are copies of the rating and stay that way
+    held: bool,
+    // readings dropped so far, None where a bit is free
+    rows: Vec<Vec<Option<bool>>>,
+}
+
+impl<'a> Side<'a> {
+    fn new(target: &'a dyn Fn(usize) -> bool, least: bool, left: usize) -> Side<'a> {
+        Side { target, least, left, held: false, rows: vec![] }
+    }
+
+    fn target(&self, position: usize) -> bool {
+        (self.target)(position)
+    }
+
+    // fewest and most readings the criterion can keep at position, only a 0 may tie for the minority
+    fn range(&self, position: usize) -> (usize, usize) {
+        if self.held {
+            return (self.left, self.left);
+        }
+        let bit = self.target(position);
+        match (self.least, bit) {
+            (true, true) => (1, (self.left - 1) / 2),
+            (true, false) => (1, self.left / 2),
+            (false, true) => (self.left.div_ceil(2), self.left),
+            (false, false) => (self.left / 2 + 1, self.left),
+        }
+    }
+
+    // dropping as many as allowed leaves the most bits free
+    fn keep(&self, position: usize) -> usize {
+        if self.left <= 1 || self.held {
+            return self.left;
+        }
+        let (least, most) = self.range(position);
+        if self.least { most } else { least }
+    }
+
+    // readings on this side with bit at position when keep of them follow target
+    fn agree(&self, position: usize, keep: usize, bit: bool) -> usize {
+        if self.target(position) == bit { keep } else { self.left - keep }
+    }
+
+    fn step(&mut self, position: usize, mut keep: usize, width: usize) {
+        if self.left <= 1 || self.held {
+            return;
+        }
+        // the minority only keeps them all once it stops dropping for good
+        if self.least && keep == self.left {
+            self.held = true;
+            return;
+        }
+        // two left on the minority side can never keep a 1
+        if self.least && keep == 2 && position + 1 < width && self.target(position + 1) {
+            keep = 1;
+        }
+        let dropout: Vec<Option<bool>> = (0..width)
+            .map(|p| match p.cmp(&position) {
+                Ordering::Less => Some(self.target(p)),
+                Ordering::Equal => Some(!self.target(p)),
+                Ordering::Greater => None,
+            })
+            .collect();
+        self.rows.extend((keep..self.left).map(|_| dropout.clone()));
+        self.left = keep;
+    }
+
+    // more readings left than positions to drop them at end up as repeats
+    fn finish(mut self, width: usize) -> Vec<Vec<Option<bool>>> {
+        let target: Vec<Option<bool>> = (0..width).map(|p| Some(self.target(p))).collect();
+        self.rows.extend((0..self.left).map(|_| target.clone()));
+        self.rows
+    }
+}
+
+// whether value has no bits set at or beyond width
+fn fits(value: &Bits, width: usize) -> bool {
+    (width..value.width().max(width)).all(|bit| !value.get(bit))
+}
+
+fn resize(value: &Bits, width: usize) -> Bits {
+    let mut resized = Bits::zero(width);
+    for bit in (0..width).filter(|bit| value.get(*bit)) {
+        resized.set(bit);
+    }
+    resized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::Duplicates;
+
+    fn spec(width: usize, len: usize, oxygen: u64, c02: u64) -> Spec {
+        Spec { width, len, gamma: None, epsilon: None, oxygen: Bits::from(oxygen), c02: Bits::from(c02), seed: 1 }
+    }
+
+    fn ratings(lines: &[String]) -> (Bits, Bits, Bits) {
+        let report = Report::parse(lines).unwrap();
+        let options = Options::default();
+        (
+            rates(&report, TiePolicy::PreferOne).unwrap().gamma,
+            filter(&report, &Criterion::oxygen(), &options).unwrap().value,
+            filter(&report, &Criterion::c02(), &options).unwrap().value,
+        )
+    }
+
+    #[test]
+    fn test_sample_targets() {
+        let mut sample = spec(5, 12, 23, 10);
+        sample.gamma = Some(Bits::from(22));
+        let lines = generate(&sample).unwrap();
+        assert_eq!(lines.len(), 12);
+        assert!(lines.iter().all(|line| line.len() == 5));
+        assert_eq!(ratings(&lines), (Bits::from(22), Bits::from(23), Bits::from(10)));
+    }
+
+    #[test]
+    fn test_targets() {
+        for (width, len, oxygen, c02, gamma) in [
+            (8, 100, 0b1111_0110, 0b0100_1001, 0b1111_0000),
+            (8, 100, 0b0000_0000, 0b1111_1111, 0b0010_0101),
+            (12, 1000, 0b1000_0000_0001, 0b0111_1111_1110, 0b1010_1010_1010),
+            (3, 2, 0b100, 0b011, 0b111),
+            (4, 3, 0b0110, 0b1001, 0b0010),
+        ] {
+            let mut target = spec(width, len, oxygen, c02);
+            target.epsilon = Some(resize(&Bits::from(!gamma), width));
+            let lines = generate(&target).unwrap();
+            assert_eq!(lines.len(), len);
+            assert_eq!(ratings(&lines), (Bits::from(gamma), Bits::from(oxygen), Bits::from(c02)));
+        }
+    }
+
+    #[test]
+    fn test_repeats() {
+        // 3 bits can't tell 100 readings apart, so the ratings come out repeated
+        let lines = generate(&spec(3, 100, 0b110, 0b001)).unwrap();
+        let report = Report::parse(&lines).unwrap();
+        let count = Options { duplicates: Duplicates::Count, ..Default::default() };
+        let rating = filter(&report, &Criterion::oxygen(), &count).unwrap();
+        assert_eq!(rating.value, Bits::from(0b110));
+        assert!(rating.multiplicity > 1);
+    }
+
+    #[test]
+    fn test_all_duplicates() {
+        let lines = generate(&spec(2, 2, 0b11, 0b11)).unwrap();
+        assert_eq!(lines, vec!["11", "11"]);
+        let mut target = spec(4, 5, 0b0110, 0b0110);
+        target.gamma = Some(Bits::from(0b0110));
+        assert_eq!(ratings(&generate(&target).unwrap()), (Bits::from(0b0110), Bits::from(0b0110), Bits::from(0b0110)));
+    }
+
+    // generates a report to the same ratings lines has, and checks it rates the same
+    fn round_trip(lines: &[String]) {
+        let report = Report::parse(lines).unwrap();
+        let options = Options::default();
+        let (Ok(rates), Ok(oxygen), Ok(c02)) = (
+            rates(&report, TiePolicy::PreferOne),
+            filter(&report, &Criterion::oxygen(), &options),
+            filter(&report, &Criterion::c02(), &options),
+        ) else {
+            return;
+        };
+        let target = Spec { gamma: Some(rates.gamma.clone()), ..spec(report.width(), lines.len(), 0, 0) };
+        let target = Spec { oxygen: oxygen.value.clone(), c02: c02.value.clone(), ..target };
+        let generated = generate(&target).unwrap_or_else(|e| panic!("{:?}: {}", lines, e));
+        assert_eq!(ratings(&generated), (rates.gamma, oxygen.value, c02.value));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        // gamma 10, oxygen 11, c02 00: the c02 side has to keep all three copies of 00
+        let lines: Vec<String> = ["00", "00", "11", "10", "00", "11"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(ratings(&lines), (Bits::from(0b10), Bits::from(0b11), Bits::from(0b00)));
+        round_trip(&lines);
+        for seed in 0..1000 {
+            round_trip(&random(1 + seed as usize % 6, 1 + seed as usize / 7 % 12, seed));
+        }
+    }
+
+    #[test]
+    fn test_unreachable() {
+        assert!(matches!(generate(&spec(4, 10, 0b1000, 0b1111)), Err(DiagnosticError::Unreachable(_))));
+        assert!(matches!(generate(&spec(4, 2, 0b0000, 0b1111)), Err(DiagnosticError::Unreachable(_))));
+        assert!(matches!(generate(&spec(4, 10, 0b10000, 0b0111)), Err(DiagnosticError::Unreachable(_))));
+        // gamma always follows oxygen at the first bit
+        let mut target = spec(4, 10, 0b1000, 0b0111);
+        target.gamma = Some(Bits::from(0b0000));
+        assert!(matches!(generate(&target), Err(DiagnosticError::Unreachable(_))));
+        target.gamma = Some(Bits::from(0b1111));
+        target.epsilon = Some(Bits::from(0b0001));
+        assert!(matches!(generate(&target), Err(DiagnosticError::Unreachable(_))));
+    }
+
+    #[test]
+    fn test_random() {
+        let lines = random(70, 50, 3);
+        assert_eq!(lines.len(), 50);
+        assert!(lines.iter().all(|line| line.len() == 70 && line.chars().all(|c| c == '0' || c == '1')));
+        assert_eq!(lines, random(70, 50, 3));
+        assert_ne!(lines, random(70, 50, 4));
+    }
+}
@@ -0,0 +1,199 @@
+use crate::bits::Bits;
+use crate::report::Report;
+
+/*
+the report packed by reading rather than by column, so the distance
+between two readings is a popcount of their xor, a word at a time.
+position p of a reading is bit p % 64 of its word p / 64.
+*/
+pub struct Rows {
+    width: usize,
+    words: usize,
+    rows: Vec<u64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Neighbour {
+    // counting from 1
+    pub line: usize,
+    pub distance: usize,
+}
+
+impl Rows {
+    pub fn from_report(report: &Report) -> Rows {
+        let words = report.width().div_ceil(64);
+        let mut rows = vec![0; report.len() * words];
+        for position in 0..report.width() {
+            for i in report.column(position).iter() {
+                rows[i * words + position / 64] |= 1 << (position % 64);
+            }
+        }
+        Rows { width: report.width(), words, rows }
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len() / self.words.max(1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    fn row(&self, i: usize) -> &[u64] {
+        &self.rows[i * self.words..(i + 1) * self.words]
+    }
+
+    // a value such as gamma laid out the same way as the rows
+    pub fn pack(&self, pattern: &Bits) -> Vec<u64> {
+        let mut packed = vec![0; self.words];
+        for position in 0..self.width {
+            if pattern.get(self.width - 1 - position) {
+                packed[position / 64] |= 1 << (position % 64);
+            }
+        }
+        packed
+    }
+
+    pub fn distance(&self, i: usize, packed: &[u64]) -> usize {
+        self.row(i).iter().zip(packed).map(|(a, b)| (a ^ b).count_ones() as usize).sum()
+    }
+
+    // every reading's distance from pattern, nearest first and then in report order
+    fn ranked(&self, pattern: &Bits) -> Vec<Neighbour> {
+        let packed = self.pack(pattern);
+        let mut ranked: Vec<_> =
+            (0..self.len()).map(|i| Neighbour { line: i + 1, distance: self.distance(i, &packed) }).collect();
+        ranked.sort_by_key(|neighbour| (neighbour.distance, neighbour.line));
+        ranked
+    }
+
+    // the k readings nearest pattern, ties going to the earlier line
+    pub fn nearest(&self, pattern: &Bits, k: usize) -> Vec<Neighbour> {
+        let mut ranked = self.ranked(pattern);
+        ranked.truncate(k);
+        ranked
+    }
+
+    // all the readings as near to pattern as any gets
+    pub fn closest(&self, pattern: &Bits) -> Vec<Neighbour> {
+        let ranked = self.ranked(pattern);
+        let best = ranked.first().map(|neighbour| neighbour.distance);
+        ranked.into_iter().take_while(|neighbour| Some(neighbour.distance) == best).collect()
+    }
+
+    /*
+    readings linked whenever they are within distance of each other, so a
+    cluster can stretch further than distance end to end. each cluster
+    lists its lines in order, and the clusters go by their first line.
+    */
+    pub fn clusters(&self, distance: usize) -> Vec<Vec<usize>> {
+        let mut parent: Vec<usize> = (0..self.len()).collect();
+        fn root(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+        for i in 0..self.len() {
+            for j in i + 1..self.len() {
+                if self.distance(j, self.row(i)) <= distance {
+                    let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                    // the earlier line stays the root, which keeps clusters in order
+                    parent[a.max(b)] = a.min(b);
+                }
+            }
+        }
+        let mut clusters: Vec<Vec<usize>> = vec![];
+        let mut index = vec![usize::MAX; self.len()];
+        for i in 0..self.len() {
+            let r = root(&mut parent, i);
+            if index[r] == usize::MAX {
+                index[r] = clusters.len();
+                clusters.push(vec![]);
+            }
+            clusters[index[r]].push(i + 1);
+        }
+        clusters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rates, TiePolicy};
+
+    fn sample() -> Report {
+        let report = vec![
+            String::from("00100"),
+            String::from("11110"),
+            String::from("10110"),
+            String::from("10111"),
+            String::from("10101"),
+            String::from("01111"),
+            String::from("00111"),
+            String::from("11100"),
+            String::from("10000"),
+            String::from("11001"),
+            String::from("00010"),
+            String::from("01010"),
+        ];
+        Report::parse(&report).unwrap()
+    }
+
+    #[test]
+    fn test_nearest() {
+        let report = sample();
+        let rows = Rows::from_report(&report);
+        let rates = rates(&report, TiePolicy::PreferOne).unwrap();
+        // gamma is 10110, which is line 3 exactly
+        assert_eq!(rows.closest(&rates.gamma), vec![Neighbour { line: 3, distance: 0 }]);
+        assert_eq!(
+            rows.nearest(&rates.gamma, 3),
+            vec![
+                Neighbour { line: 3, distance: 0 },
+                Neighbour { line: 2, distance: 1 },
+                Neighbour { line: 4, distance: 1 },
+            ]
+        );
+        // epsilon is 01001, and only 11001 is one away
+        assert_eq!(rows.closest(&rates.eps), vec![Neighbour { line: 10, distance: 1 }]);
+    }
+
+    #[test]
+    fn test_wide() {
+        let far = format!("1{}", "0".repeat(99));
+        let near = format!("{}1", "0".repeat(99));
+        let report = Report::parse(&[far, near, "0".repeat(100)]).unwrap();
+        let rows = Rows::from_report(&report);
+        let mut pattern = Bits::zero(100);
+        pattern.set(0);
+        pattern.set(1);
+        assert_eq!(
+            rows.nearest(&pattern, 3),
+            vec![
+                Neighbour { line: 2, distance: 1 },
+                Neighbour { line: 3, distance: 2 },
+                Neighbour { line: 1, distance: 3 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_clusters() {
+        let report = Report::parse(&[
+            String::from("0000"),
+            String::from("1111"),
+            String::from("0001"),
+            String::from("1110"),
+            String::from("0011"),
+            String::from("1000"),
+        ])
+        .unwrap();
+        let rows = Rows::from_report(&report);
+        assert_eq!(rows.clusters(0), vec![vec![1], vec![2], vec![3], vec![4], vec![5], vec![6]]);
+        // 0000 0001 0011 chain together, as do 1111 1110, with 1000 next to 0000
+        assert_eq!(rows.clusters(1), vec![vec![1, 3, 5, 6], vec![2, 4]]);
+        assert_eq!(rows.clusters(4), vec![vec![1, 2, 3, 4, 5, 6]]);
+    }
+}
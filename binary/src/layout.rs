@@ -0,0 +1,127 @@
+use std::str::FromStr;
+
+use crate::bits::Bits;
+use crate::error::DiagnosticError;
+
+// the order oxygen and c02 go through the positions in
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Order {
+    #[default]
+    MsbFirst,
+    LsbFirst,
+    // positions counted from the left, each exactly once
+    Permutation(Vec<usize>),
+}
+
+impl FromStr for Order {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Order, String> {
+        match s {
+            "msb" => Ok(Order::MsbFirst),
+            "lsb" => Ok(Order::LsbFirst),
+            _ => s
+                .split(',')
+                .map(|position| position.trim().parse().map_err(|_| format!("invalid order {}", s)))
+                .collect::<Result<_, _>>()
+                .map(Order::Permutation),
+        }
+    }
+}
+
+// which positions take part, written like a reading with 1 for the ones that do
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mask(Vec<bool>);
+
+impl FromStr for Mask {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Mask, String> {
+        s.chars()
+            .map(|c| match c {
+                '1' => Ok(true),
+                '0' => Ok(false),
+                _ => Err(format!("invalid mask {}", s)),
+            })
+            .collect::<Result<_, _>>()
+            .map(Mask)
+    }
+}
+
+/*
+how the positions of a binary report are read. masked out positions are
+left out of the filtering and come out as 0 in gamma, epsilon and the
+ratings, the same as if the report never had them set.
+*/
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Layout {
+    pub order: Order,
+    pub mask: Option<Mask>,
+}
+
+impl Layout {
+    // the positions to consider for a report of width, in order
+    pub fn positions(&self, width: usize) -> Result<Vec<usize>, DiagnosticError> {
+        let order = match &self.order {
+            Order::MsbFirst => (0..width).collect(),
+            Order::LsbFirst => (0..width).rev().collect(),
+            Order::Permutation(order) => {
+                let mut seen = vec![false; width];
+                for position in order {
+                    if *position >= width || std::mem::replace(&mut seen[*position], true) {
+                        return Err(DiagnosticError::Layout(format!("position {} is repeated or out of range", position)));
+                    }
+                }
+                if order.len() < width {
+                    return Err(DiagnosticError::Layout(format!("order has {} of {} positions", order.len(), width)));
+                }
+                order.clone()
+            }
+        };
+        match &self.mask {
+            None => Ok(order),
+            Some(Mask(mask)) if mask.len() != width => {
+                Err(DiagnosticError::Layout(format!("mask has {} bits for a report of {}", mask.len(), width)))
+            }
+            Some(Mask(mask)) => Ok(order.into_iter().filter(|position| mask[*position]).collect()),
+        }
+    }
+
+    pub fn includes(&self, position: usize) -> bool {
+        self.mask.as_ref().is_none_or(|Mask(mask)| mask.get(position).is_some_and(|bit| *bit))
+    }
+}
+
+// value with only the bits at positions kept
+pub fn keep(value: &Bits, positions: &[usize]) -> Bits {
+    let width = value.width();
+    let mut kept = Bits::zero(width);
+    for position in positions {
+        if value.get(width - 1 - position) {
+            kept.set(width - 1 - position);
+        }
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_positions() {
+        assert_eq!(Layout::default().positions(4), Ok(vec![0, 1, 2, 3]));
+        let lsb = Layout { order: "lsb".parse().unwrap(), mask: None };
+        assert_eq!(lsb.positions(4), Ok(vec![3, 2, 1, 0]));
+        let masked = Layout { order: "2,0,3,1".parse().unwrap(), mask: Some("1101".parse().unwrap()) };
+        assert_eq!(masked.positions(4), Ok(vec![0, 3, 1]));
+        assert!(!masked.includes(2));
+        assert!(masked.positions(5).is_err());
+        let repeated = Layout { order: "0,1,1".parse().unwrap(), mask: None };
+        assert!(repeated.positions(3).is_err());
+        let short = Layout { order: "0,1".parse().unwrap(), mask: None };
+        assert!(short.positions(3).is_err());
+        assert!("1,x".parse::<Order>().is_err());
+        assert!("1021".parse::<Mask>().is_err());
+    }
+}
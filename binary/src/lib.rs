@@ -0,0 +1,316 @@
+pub mod analytics;
+pub mod bits;
+pub mod bitset;
+pub mod drift;
+pub mod error;
+pub mod filter;
+pub mod generate;
+pub mod hamming;
+pub mod layout;
+pub mod radix;
+pub mod report;
+pub mod sensitivity;
+pub mod stream;
+pub mod trace;
+pub mod trie;
+
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use bits::Bits;
+use bitset::BitSet;
+use error::DiagnosticError;
+use filter::{filter, Criterion, Options, Rating};
+use layout::Layout;
+use report::Report;
+use trie::Trie;
+
+pub fn get_report(filename: String) -> Result<Vec<String>, DiagnosticError> {
+    Ok(std::fs::read_to_string(&filename)
+        .map_err(|e| DiagnosticError::Io(format!("could not read {}: {}", filename, e)))?
+        .lines()
+        .map(String::from)
+        .collect())
+}
+
+pub fn power_consumption(gamma: &Bits, eps: &Bits) -> Bits {
+    gamma * eps
+}
+
+// both ratings from one trie, rather than filtering the report twice
+pub fn life_support(report: &Report, options: &Options) -> Result<Bits, DiagnosticError> {
+    let trie = Trie::with_layout(report, &options.layout)?;
+    let oxygen = trie.rating(&Criterion::oxygen(), options)?;
+    let c02 = trie.rating(&Criterion::c02(), options)?;
+    Ok(&oxygen.value * &c02.value)
+}
+
+pub struct Rates {
+    pub gamma: Bits,
+    pub eps: Bits
+}
+
+// how to settle a position with as many "1"s as "0"s
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TiePolicy {
+    #[default]
+    PreferOne,
+    PreferZero,
+    Error
+}
+
+impl FromStr for TiePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<TiePolicy, String> {
+        match s {
+            "one" => Ok(TiePolicy::PreferOne),
+            "zero" => Ok(TiePolicy::PreferZero),
+            "error" => Ok(TiePolicy::Error),
+            _ => Err(format!("invalid tie policy {}", s))
+        }
+    }
+}
+
+impl TiePolicy {
+    pub fn settle(self, position: usize) -> Result<u32, DiagnosticError> {
+        match self {
+            TiePolicy::PreferOne => Ok(1),
+            TiePolicy::PreferZero => Ok(0),
+            TiePolicy::Error => Err(DiagnosticError::Tie { position })
+        }
+    }
+
+    // most common bit when ones of total readings have a 1
+    pub fn majority(self, ones: usize, total: usize, position: usize) -> Result<u32, DiagnosticError> {
+        match ones.cmp(&(total - ones)) {
+            Ordering::Greater => Ok(1),
+            Ordering::Less => Ok(0),
+            Ordering::Equal => self.settle(position)
+        }
+    }
+}
+
+// ones at each position across the whole report
+pub fn counts(report: &Report) -> Vec<usize> {
+    report.counts(&report.all())
+}
+
+pub fn rates(report: &Report, tie: TiePolicy) -> Result<Rates, DiagnosticError> {
+    rates_with(report, tie, &Layout::default())
+}
+
+// masked out positions are 0 in both gamma and epsilon, and never tie
+pub fn rates_with(report: &Report, tie: TiePolicy, layout: &Layout) -> Result<Rates, DiagnosticError> {
+    let positions = layout.positions(report.width())?;
+    let total = report.total(&report.all());
+    let mut g = Bits::zero(report.width());
+    for (i, ones) in counts(report).into_iter().enumerate() {
+        if layout.includes(i) && tie.majority(ones, total, i)? == 1 {
+            g.set(report.width() - 1 - i);
+        }
+    }
+    Ok(Rates{
+        eps: layout::keep(&g.not(), &positions),
+        gamma: g
+    })
+}
+
+pub fn most_common(report: &Report, candidates: &BitSet, tie: TiePolicy, nth_bit: usize) -> Result<u32, DiagnosticError> {
+    tie.majority(report.ones(nth_bit, candidates), report.total(candidates), nth_bit)
+}
+
+pub fn oxygen(report: &Report, options: &Options) -> Result<Rating, DiagnosticError> {
+    filter(report, &Criterion::oxygen(), options)
+}
+
+pub fn c02(report: &Report, options: &Options) -> Result<Rating, DiagnosticError> {
+    filter(report, &Criterion::c02(), options)
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    /* diagnostic report
+    00100
+    11110
+    10110
+    10111
+    10101
+    01111
+    00111
+    11100
+    10000
+    11001
+    00010
+    01010
+
+    gamma is most common bit in each position
+    epsilon is least common bit in each position (!gamma)
+    when they are equal the TiePolicy decides
+     */
+
+    #[test]
+    fn test_power() {
+        assert_eq!(power_consumption(&Bits::from(9), &Bits::from(22)), Bits::from(198));
+    }
+
+    #[test]
+    fn test_rates() {
+        let report = vec![
+            String::from("00100"),
+            String::from("11110"),
+            String::from("10110"),
+            String::from("10111"),
+            String::from("10101"),
+            String::from("01111"),
+            String::from("00111"),
+            String::from("11100"),
+            String::from("10000"),
+            String::from("11001"),
+            String::from("00010"),
+            String::from("01010"),
+        ];
+        let report = Report::parse(&report).unwrap();
+        let rates = rates(&report, TiePolicy::PreferOne).unwrap();
+        assert_eq!(rates.gamma, Bits::from(22));
+        assert_eq!(rates.eps, Bits::from(9));
+    }
+
+    #[test]
+    fn test_rates_odd() {
+        // 3 of 5 is a majority
+        let report = vec![
+            String::from("110"),
+            String::from("101"),
+            String::from("100"),
+            String::from("011"),
+            String::from("000"),
+        ];
+        let report = Report::parse(&report).unwrap();
+        let rates = rates(&report, TiePolicy::Error).unwrap();
+        assert_eq!(rates.gamma, Bits::from(0b100));
+        assert_eq!(rates.eps, Bits::from(0b011));
+    }
+
+    #[test]
+    fn test_rates_even() {
+        // first position is tied, second is 3 of 4
+        let report = vec![
+            String::from("11"),
+            String::from("11"),
+            String::from("01"),
+            String::from("00"),
+        ];
+        let report = Report::parse(&report).unwrap();
+        assert_eq!(rates(&report, TiePolicy::PreferOne).unwrap().gamma, Bits::from(0b11));
+        assert_eq!(rates(&report, TiePolicy::PreferZero).unwrap().gamma, Bits::from(0b01));
+        assert_eq!(rates(&report, TiePolicy::Error).err(), Some(DiagnosticError::Tie { position: 0 }));
+    }
+
+    #[test]
+    fn test_rates_masked() {
+        let report = vec![
+            String::from("11"),
+            String::from("11"),
+            String::from("01"),
+            String::from("00"),
+        ];
+        let report = Report::parse(&report).unwrap();
+        // the tied position is masked out, so it can't be an error
+        let layout = Layout { mask: Some("01".parse().unwrap()), ..Default::default() };
+        let rates = rates_with(&report, TiePolicy::Error, &layout).unwrap();
+        assert_eq!((rates.gamma, rates.eps), (Bits::from(0b01), Bits::from(0b00)));
+    }
+
+    #[test]
+    fn test_most_common() {
+        let report = Report::parse(&[String::from("10"), String::from("01"), String::from("11")]).unwrap();
+        let all = report.all();
+        assert_eq!(most_common(&report, &all, TiePolicy::Error, 0), Ok(1));
+        let mut pair = BitSet::new(3);
+        pair.insert(0);
+        pair.insert(1);
+        assert_eq!(most_common(&report, &pair, TiePolicy::PreferZero, 0), Ok(0));
+        assert_eq!(most_common(&report, &pair, TiePolicy::PreferOne, 1), Ok(1));
+        assert_eq!(most_common(&report, &pair, TiePolicy::Error, 1), Err(DiagnosticError::Tie { position: 1 }));
+    }
+
+    #[test]
+    fn test_oxygen() {
+        let report = vec![
+            String::from("00100"),
+            String::from("11110"),
+            String::from("10110"),
+            String::from("10111"),
+            String::from("10101"),
+            String::from("01111"),
+            String::from("00111"),
+            String::from("11100"),
+            String::from("10000"),
+            String::from("11001"),
+            String::from("00010"),
+            String::from("01010"),
+        ];
+        let report = Report::parse(&report).unwrap();
+        assert_eq!(oxygen(&report, &Options::default()).unwrap(), Rating { value: Bits::from(23), position: Some(4), multiplicity: 1 });
+    }
+
+    #[test]
+    fn test_c02() {
+        let report = vec![
+            String::from("00100"),
+            String::from("11110"),
+            String::from("10110"),
+            String::from("10111"),
+            String::from("10101"),
+            String::from("01111"),
+            String::from("00111"),
+            String::from("11100"),
+            String::from("10000"),
+            String::from("11001"),
+            String::from("00010"),
+            String::from("01010"),
+        ];
+        let report = Report::parse(&report).unwrap();
+        assert_eq!(c02(&report, &Options::default()).unwrap(), Rating { value: Bits::from(10), position: Some(2), multiplicity: 1 });
+    }
+
+    #[test]
+    fn test_life_support() {
+        let report = vec![
+            String::from("00100"),
+            String::from("11110"),
+            String::from("10110"),
+            String::from("10111"),
+            String::from("10101"),
+            String::from("01111"),
+            String::from("00111"),
+            String::from("11100"),
+            String::from("10000"),
+            String::from("11001"),
+            String::from("00010"),
+            String::from("01010"),
+        ];
+        let report = Report::parse(&report).unwrap();
+        assert_eq!(life_support(&report, &Options::default()).unwrap(), Bits::from(230));
+    }
+
+    #[test]
+    fn test_exhausted() {
+        // 000 and 001 are left after the first bit, and neither has the least common 1
+        let report = vec![
+            String::from("000"),
+            String::from("001"),
+            String::from("110"),
+            String::from("111"),
+            String::from("101"),
+        ];
+        let report = Report::parse(&report).unwrap();
+        assert_eq!(oxygen(&report, &Options::default()).unwrap().value, Bits::from(0b111));
+        assert_eq!(c02(&report, &Options::default()), Err(DiagnosticError::Exhausted { position: 1 }));
+        assert_eq!(life_support(&report, &Options::default()), Err(DiagnosticError::Exhausted { position: 1 }));
+    }
+}
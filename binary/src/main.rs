@@ -1,199 +1,217 @@
-use std::{collections::HashMap};
+use std::str::FromStr;
+
+use binary::analytics;
+use binary::drift;
+use binary::bits::Bits;
+use binary::error::DiagnosticError;
+use binary::filter::{Criterion, Options, Rating};
+use binary::generate::{self, Spec};
+use binary::hamming::Rows;
+use binary::layout::Layout;
+use binary::radix::{self, DigitCriterion, DigitReport};
+use binary::report::Report;
+use binary::sensitivity;
+use binary::stream;
+use binary::trace::trace;
+use binary::{c02, get_report, life_support, oxygen, power_consumption, rates_with, TiePolicy};
 
 fn main() {
-    let report = get_report("diagnostic_report".into());
-    let rates = rates(&report);
-    let power = power_consumption(rates.gamma, rates.eps);
-    println!("power: {}", power);
-    let ls = life_support(&report);
-    println!("life support: {}", ls);
-
-}
-
-fn get_report(filename: String) -> Vec<String> {
-    std::fs::read_to_string(filename)
-        .expect("file not found")
-        .lines()
-        .map(|x| x.parse::<String>().expect("invalid line"))
-        .collect()
-}
-
-fn power_consumption(gamma: i32, eps: i32) -> i32 {
-    gamma * eps
-}
-
-fn life_support(report: &Vec<String>) -> i32 {
-    oxygen(report, 0) * c02(report, 0)
-}
-
-struct Rates {
-    gamma: i32,
-    eps: i32
+    if let Err(e) = run() {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
 }
 
-fn rates(report: &Vec<String>) -> Rates {
-    let mut counts = HashMap::new();
-    for line in report {
-        for (i, c) in line.chars().enumerate() {
-            *counts.entry(i).or_insert(0) += char::to_digit(c, 10).expect("invalid char");
+fn run() -> Result<(), DiagnosticError> {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match args.first().map(String::as_str) {
+        Some("analytics") | Some("drift") | Some("generate") | Some("hamming") | Some("rates") | Some("sensitivity") | Some("trace") => args.remove(0),
+        _ => String::from("ratings"),
+    };
+    let mut filename = String::from("diagnostic_report");
+    let mut tie = TiePolicy::default();
+    let mut options = Options::default();
+    let mut radix = 2;
+    let mut json = false;
+    let mut spec = Spec { width: 0, len: 0, gamma: None, epsilon: None, oxygen: Bits::zero(0), c02: Bits::zero(0), seed: 0 };
+    let mut targets = (None, None);
+    let mut pattern = None;
+    let mut k = 1;
+    let mut within = None;
+    let mut window = None;
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--tie" => tie = value(&mut rest, "tie policy")?,
+            "--duplicates" => options.duplicates = value(&mut rest, "duplicates policy")?,
+            "--json" => json = true,
+            "--window" => window = Some(value(&mut rest, "window")?),
+            "--pattern" => pattern = Some(binary_value(&value::<String>(&mut rest, "pattern")?)?),
+            "--k" => k = value(&mut rest, "k")?,
+            "--within" => within = Some(value(&mut rest, "distance")?),
+            "--order" => options.layout.order = value(&mut rest, "order")?,
+            "--mask" => options.layout.mask = Some(value(&mut rest, "mask")?),
+            "--width" => spec.width = value(&mut rest, "width")?,
+            "--len" => spec.len = value(&mut rest, "length")?,
+            "--seed" => spec.seed = value(&mut rest, "seed")?,
+            "--gamma" => spec.gamma = Some(binary_value(&value::<String>(&mut rest, "gamma")?)?),
+            "--epsilon" => spec.epsilon = Some(binary_value(&value::<String>(&mut rest, "epsilon")?)?),
+            "--oxygen" => targets.0 = Some(binary_value(&value::<String>(&mut rest, "oxygen")?)?),
+            "--c02" => targets.1 = Some(binary_value(&value::<String>(&mut rest, "c02")?)?),
+            "--radix" => radix = value(&mut rest, "radix")?,
+            _ => filename = arg.clone(),
         }
     }
-    let mut g = String::from("");
-    let mut e = String::from("");
-    let size = report.len() as f32;
-    for i in 0..report.first().unwrap().chars().count() {
-        let c = counts.get(&i).unwrap();
-        if *c > (size / 2.0).ceil() as u32 {
-            g.push_str("1");
-            e.push_str("0");
-        } else {
-            g.push_str("0");
-            e.push_str("1");
+
+    if command == "generate" {
+        let incomplete = |reason: &str| Err(DiagnosticError::Unreachable(String::from(reason)));
+        let lines = match targets {
+            (Some(oxygen), Some(c02)) => {
+                // the ratings give the width when it isn't set
+                if spec.width == 0 {
+                    spec.width = oxygen.width();
+                }
+                (spec.oxygen, spec.c02) = (oxygen, c02);
+                generate::generate(&spec)?
+            }
+            (None, None) if spec.gamma.is_none() && spec.epsilon.is_none() => {
+                if spec.width == 0 {
+                    return incomplete("a random report needs --width");
+                }
+                generate::random(spec.width, spec.len, spec.seed)
+            }
+            _ => return incomplete("targets need both --oxygen and --c02"),
+        };
+        for line in lines {
+            println!("{}", line);
         }
+        return Ok(());
     }
-    Rates{
-        gamma: i32::from_str_radix(&g, 2).unwrap(),
-        eps: i32::from_str_radix(&e, 2).unwrap()
+    if radix != 2 && options.layout != Layout::default() {
+        return Err(DiagnosticError::Layout(String::from("order and mask only apply to binary reports")));
     }
-}
-
-/*
-1 == "1"
-0 == same number of "1" and "0"
--1 == "0"
-*/
-fn most_common(report: &Vec<String>, precedence: u32, nth_bit: u32) -> u32 {
-    let mut sum = 0;
-    for bits in report {
-        if bits.chars().nth(nth_bit as usize).unwrap() == '1' {
-            sum += 1;
+    if command == "rates" && radix == 2 {
+        let rates = if options.layout == Layout::default() {
+            // counted straight off the file, without holding the report
+            let file = std::fs::File::open(&filename)
+                .map_err(|e| DiagnosticError::Io(format!("could not read {}: {}", filename, e)))?;
+            stream::rates(std::io::BufReader::new(file), tie)?
         } else {
-            sum -= 1;
-        }
-    }
-    if sum > 0 {
-        1
-    } else if sum == 0 {
-        precedence
-    } else {
-        0
+            rates_with(&Report::parse(&get_report(filename)?)?, tie, &options.layout)?
+        };
+        println!("gamma: {}", rates.gamma);
+        println!("epsilon: {}", rates.eps);
+        let power = power_consumption(&rates.gamma, &rates.eps);
+        println!("power: {} ({:#x})", power, power);
+        return Ok(());
     }
-}
 
-fn oxygen(report: &Vec<String>, nth_bit: u32) -> i32 {
-    if report.len() == 1 {
-        return i32::from_str_radix(report.first().unwrap(), 2).unwrap();
+    let lines = get_report(filename)?;
+    if radix != 2 {
+        return digit_ratings(&DigitReport::parse(&lines, radix)?, tie, &options);
     }
-    let most_common = most_common(report, 1, nth_bit);
-    let mut next_bits = report
-        .into_iter()
-        .filter(
-            |b| char::to_digit(b.chars().nth(nth_bit as usize).unwrap(), 10).unwrap() == most_common
-        )
-        .cloned()
-        .collect::<Vec<_>>();
-    return oxygen(&next_bits, nth_bit + 1);
-}
-
-fn c02(report: &Vec<String>, nth_bit: u32) -> i32 {
-    if report.len() == 1 {
-        return i32::from_str_radix(report.first().unwrap(), 2).unwrap();
+    let report = Report::parse(&lines)?;
+    match command.as_str() {
+        "analytics" => print!("{}", analytics::analyse(&report)),
+        "drift" => {
+            let window = window.unwrap_or(report.len().min(10));
+            print!("{}", drift::drift(&report, window, tie, &options.layout)?)
+        }
+        "hamming" => similarity(&report, tie, &options, pattern, k, within)?,
+        "sensitivity" => print!("{}", sensitivity::analyse(&report, &options)?),
+        "trace" => {
+            let oxygen = trace(&report, &Criterion::oxygen(), &options);
+            let c02 = trace(&report, &Criterion::c02(), &options);
+            if json {
+                println!(r#"{{"oxygen":{},"c02":{}}}"#, oxygen.json(), c02.json());
+            } else {
+                print!("oxygen\n{}\nc02\n{}", oxygen.text(), c02.text());
+            }
+        }
+        _ => ratings(&report, tie, &options)?,
     }
-    let most_common = most_common(report, 1, nth_bit);
-    let mut next_bits = report
-        .into_iter()
-        .filter(
-            |b| char::to_digit(b.chars().nth(nth_bit as usize).unwrap(), 10).unwrap() != most_common
-        )
-        .cloned()
-        .collect::<Vec<_>>();
-    return c02(&next_bits, nth_bit + 1);
+    Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    // Note this useful idiom: importing names from outer (for mod tests) scope.
-    use super::*;
-
-    /* diagnostic report
-    00100
-    11110
-    10110
-    10111
-    10101
-    01111
-    00111
-    11100
-    10000
-    11001
-    00010
-    01010
+fn ratings(report: &Report, tie: TiePolicy, options: &Options) -> Result<(), DiagnosticError> {
+    let rates = rates_with(report, tie, &options.layout)?;
+    let power = power_consumption(&rates.gamma, &rates.eps);
+    println!("power: {} ({:#x})", power, power);
+    print_rating("oxygen", &oxygen(report, options)?);
+    print_rating("c02", &c02(report, options)?);
+    let ls = life_support(report, options)?;
+    println!("life support: {} ({:#x})", ls, ls);
+    Ok(())
+}
 
-    gamma is most common bit in each position
-    epsilon is least common bit in each position (!gamma)
-    what if they are equal?
-     */
+fn digit_ratings(report: &DigitReport, tie: TiePolicy, options: &Options) -> Result<(), DiagnosticError> {
+    let rates = radix::rates(report, tie)?;
+    let power = power_consumption(&rates.gamma, &rates.eps);
+    println!("power: {} ({:#x})", power, power);
+    let oxygen = radix::filter(report, &DigitCriterion::oxygen(), options)?;
+    let c02 = radix::filter(report, &DigitCriterion::c02(), options)?;
+    print_rating("oxygen", &oxygen);
+    print_rating("c02", &c02);
+    let ls = &oxygen.value * &c02.value;
+    println!("life support: {} ({:#x})", ls, ls);
+    Ok(())
+}
 
-    #[test]
-    fn test_power() {
-        assert_eq!(power_consumption(9, 22), 198);
+// readings nearest a pattern, or gamma and epsilon, and clusters when asked for
+fn similarity(
+    report: &Report,
+    tie: TiePolicy,
+    options: &Options,
+    pattern: Option<Bits>,
+    k: usize,
+    within: Option<usize>,
+) -> Result<(), DiagnosticError> {
+    let rows = Rows::from_report(report);
+    let targets = match pattern {
+        Some(pattern) if pattern.width() != report.width() => {
+            return Err(DiagnosticError::Pattern { width: pattern.width(), expected: report.width() });
+        }
+        Some(pattern) => vec![("pattern", pattern)],
+        None => {
+            let rates = rates_with(report, tie, &options.layout)?;
+            vec![("gamma", rates.gamma), ("epsilon", rates.eps)]
+        }
+    };
+    for (name, target) in targets {
+        println!("nearest {} {:b}:", name, target);
+        for neighbour in rows.nearest(&target, k) {
+            println!("  line {} ({:b}), distance {}", neighbour.line, report.reading(neighbour.line - 1), neighbour.distance);
+        }
     }
-
-    #[test]
-    fn test_rates() {
-        let report = vec![
-            String::from("00100"),
-            String::from("11110"),
-            String::from("10110"),
-            String::from("10111"),
-            String::from("10101"),
-            String::from("01111"),
-            String::from("00111"),
-            String::from("11100"),
-            String::from("10000"),
-            String::from("11001"),
-            String::from("00010"),
-            String::from("01010"),
-        ];
-        assert_eq!(rates(&report).gamma, 22);
-        assert_eq!(rates(&report).eps, 9);
+    if let Some(distance) = within {
+        println!("clusters within {}:", distance);
+        for cluster in rows.clusters(distance) {
+            println!("  {}", cluster.iter().map(|line| line.to_string()).collect::<Vec<_>>().join(" "));
+        }
     }
+    Ok(())
+}
 
-    #[test]
-    fn test_oxygen() {
-        let report = vec![
-            String::from("00100"),
-            String::from("11110"),
-            String::from("10110"),
-            String::from("10111"),
-            String::from("10101"),
-            String::from("01111"),
-            String::from("00111"),
-            String::from("11100"),
-            String::from("10000"),
-            String::from("11001"),
-            String::from("00010"),
-            String::from("01010"),
-        ];
-        assert_eq!(oxygen(&report, 0), 23);
-    }
+// the value following a flag, name says what it is when it's missing or doesn't parse
+fn value<'a, T: FromStr>(rest: &mut impl Iterator<Item = &'a String>, name: &str) -> Result<T, DiagnosticError> {
+    let value = rest.next().ok_or_else(|| DiagnosticError::Argument(format!("missing {}", name)))?;
+    value.parse().map_err(|_| DiagnosticError::Argument(format!("invalid {} {:?}", name, value)))
+}
+
+// a rating written the way it would appear in the report
+fn binary_value(digits: &str) -> Result<Bits, DiagnosticError> {
+    let digits = digits
+        .chars()
+        .map(|c| c.to_digit(2).map(|digit| digit as u8))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(|| DiagnosticError::Argument(format!("invalid binary value {:?}", digits)))?;
+    Ok(Bits::from_digits(&digits, 2))
+}
 
-    #[test]
-    fn test_c02() {
-        let report = vec![
-            String::from("00100"),
-            String::from("11110"),
-            String::from("10110"),
-            String::from("10111"),
-            String::from("10101"),
-            String::from("01111"),
-            String::from("00111"),
-            String::from("11100"),
-            String::from("10000"),
-            String::from("11001"),
-            String::from("00010"),
-            String::from("01010"),
-        ];
-        assert_eq!(c02(&report, 0), 10);
+fn print_rating(name: &str, rating: &Rating) {
+    if rating.multiplicity > 1 {
+        println!("{}: {} ({} readings)", name, rating.value, rating.multiplicity);
+    } else {
+        println!("{}: {}", name, rating.value);
     }
 }
@@ -0,0 +1,283 @@
+use crate::bits::Bits;
+use crate::error::DiagnosticError;
+use crate::filter::{Options, Rating};
+use crate::report::{add_weight, split_weight};
+use crate::{Rates, TiePolicy};
+
+/*
+a report of readings in any radix from 2 to 36, one digit per position.
+ties between digits follow TiePolicy, with PreferOne taking the highest of
+the tied digits and PreferZero the lowest. epsilon settles its ties the
+other way round from gamma, and a position where every reading has the
+same digit gets a digit none of them have, so at radix 2 epsilon is the
+complement of gamma just as on the binary report.
+*/
+#[derive(Clone, Debug)]
+pub struct DigitReport {
+    radix: u32,
+    width: usize,
+    readings: Vec<Vec<u8>>,
+    // repeat counts, the same as on binary reports
+    weights: Vec<usize>,
+}
+
+impl DigitReport {
+    pub fn parse(lines: &[String], radix: u32) -> Result<DigitReport, DiagnosticError> {
+        if !(2..=36).contains(&radix) {
+            return Err(DiagnosticError::Radix(radix));
+        }
+        let width = split_weight(lines.first().ok_or(DiagnosticError::Empty)?, 1)?.0.chars().count();
+        let mut readings = Vec::with_capacity(lines.len());
+        let mut weights = Vec::with_capacity(lines.len());
+        let mut total = 0;
+        for (i, line) in lines.iter().enumerate() {
+            let (line, weight) = split_weight(line, i + 1)?;
+            total = add_weight(total, weight, i + 1)?;
+            weights.push(weight);
+            let mut reading = Vec::with_capacity(width);
+            for (position, c) in line.chars().enumerate() {
+                if position == width {
+                    return Err(DiagnosticError::Ragged { line: i + 1, position, width });
+                }
+                let digit = c
+                    .to_digit(radix)
+                    .ok_or(DiagnosticError::InvalidChar { line: i + 1, position, found: c })?;
+                reading.push(digit as u8);
+            }
+            if reading.len() < width {
+                return Err(DiagnosticError::Ragged { line: i + 1, position: reading.len(), width });
+            }
+            readings.push(reading);
+        }
+        Ok(DigitReport { radix, width, readings, weights })
+    }
+
+    pub fn radix(&self) -> u32 {
+        self.radix
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    // how many of candidates have each digit at position, each counted weight times
+    pub fn counts(&self, position: usize, candidates: &[usize]) -> Vec<usize> {
+        let mut counts = vec![0; self.radix as usize];
+        for i in candidates {
+            counts[self.readings[*i][position] as usize] += self.weights[*i];
+        }
+        counts
+    }
+
+    // readings in candidates, counting every repeat
+    fn total(&self, candidates: &[usize]) -> usize {
+        candidates.iter().map(|i| self.weights[*i]).sum()
+    }
+}
+
+/*
+which digit to keep at each position. least common only counts digits
+that turn up, unless just the one does: then it is a digit that doesn't,
+the highest under PreferOne and the lowest otherwise, which at radix 2 is
+the bit the binary criterion keeps.
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DigitCriterion {
+    MostCommon(TiePolicy),
+    LeastCommon(TiePolicy),
+}
+
+impl DigitCriterion {
+    pub fn oxygen() -> DigitCriterion {
+        DigitCriterion::MostCommon(TiePolicy::PreferOne)
+    }
+
+    pub fn c02() -> DigitCriterion {
+        DigitCriterion::LeastCommon(TiePolicy::PreferZero)
+    }
+
+    pub fn keep(&self, counts: &[usize], position: usize) -> Result<u8, DiagnosticError> {
+        if let DigitCriterion::LeastCommon(tie) = self {
+            if counts.iter().filter(|c| **c > 0).count() == 1 {
+                let mut missing = (0..counts.len()).filter(|d| counts[*d] == 0);
+                let digit = if *tie == TiePolicy::PreferOne { missing.next_back() } else { missing.next() };
+                return Ok(digit.unwrap() as u8);
+            }
+        }
+        let (tie, best) = match self {
+            DigitCriterion::MostCommon(tie) => (tie, counts.iter().max()),
+            DigitCriterion::LeastCommon(tie) => (tie, counts.iter().filter(|c| **c > 0).min()),
+        };
+        let best = *best.ok_or(DiagnosticError::Empty)?;
+        let mut tied = (0..counts.len()).filter(|d| counts[*d] == best);
+        let (lowest, highest) = (tied.next().unwrap(), tied.next_back());
+        match (highest, tie) {
+            (None, _) => Ok(lowest as u8),
+            (Some(highest), TiePolicy::PreferOne) => Ok(highest as u8),
+            (Some(_), TiePolicy::PreferZero) => Ok(lowest as u8),
+            (Some(_), TiePolicy::Error) => Err(DiagnosticError::Tie { position }),
+        }
+    }
+}
+
+// gamma takes the most common digit at each position and epsilon the least common
+pub fn rates(report: &DigitReport, tie: TiePolicy) -> Result<Rates, DiagnosticError> {
+    let all: Vec<usize> = (0..report.readings.len()).collect();
+    let mut gamma = Vec::with_capacity(report.width);
+    let mut eps = Vec::with_capacity(report.width);
+    for position in 0..report.width {
+        let counts = report.counts(position, &all);
+        gamma.push(DigitCriterion::MostCommon(tie).keep(&counts, position)?);
+        eps.push(DigitCriterion::LeastCommon(opposite(tie)).keep(&counts, position)?);
+    }
+    Ok(Rates { gamma: Bits::from_digits(&gamma, report.radix), eps: Bits::from_digits(&eps, report.radix) })
+}
+
+// the tie policy that picks the other end of a tie
+fn opposite(tie: TiePolicy) -> TiePolicy {
+    match tie {
+        TiePolicy::PreferOne => TiePolicy::PreferZero,
+        TiePolicy::PreferZero => TiePolicy::PreferOne,
+        TiePolicy::Error => TiePolicy::Error,
+    }
+}
+
+// as the binary filter, a digit none of the candidates have is only fine when they are all the same reading
+pub fn filter(report: &DigitReport, criterion: &DigitCriterion, options: &Options) -> Result<Rating, DiagnosticError> {
+    let mut candidates: Vec<usize> = (0..report.readings.len()).collect();
+    let mut decided = None;
+    let mut position = 0;
+    while report.total(&candidates) > 1 && position < report.width {
+        let digit = criterion.keep(&report.counts(position, &candidates), position)?;
+        let before = candidates.clone();
+        candidates.retain(|i| report.readings[*i][position] == digit);
+        if candidates.is_empty() {
+            let first = &report.readings[before[0]][position..];
+            if before.iter().all(|i| report.readings[*i][position..] == *first) {
+                candidates = before;
+                break;
+            }
+            return Err(DiagnosticError::Exhausted { position });
+        }
+        decided = Some(position);
+        position += 1;
+    }
+    let first = candidates[0];
+    let value = Bits::from_digits(&report.readings[first], report.radix);
+    options.duplicates.resolve(value, decided, report.total(&candidates), first + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::Duplicates;
+
+    fn sample() -> Vec<String> {
+        vec![
+            String::from("00100"),
+            String::from("11110"),
+            String::from("10110"),
+            String::from("10111"),
+            String::from("10101"),
+            String::from("01111"),
+            String::from("00111"),
+            String::from("11100"),
+            String::from("10000"),
+            String::from("11001"),
+            String::from("00010"),
+            String::from("01010"),
+        ]
+    }
+
+    #[test]
+    fn test_radix_two() {
+        let report = DigitReport::parse(&sample(), 2).unwrap();
+        let rates = rates(&report, TiePolicy::PreferOne).unwrap();
+        assert_eq!((rates.gamma, rates.eps), (Bits::from(22), Bits::from(9)));
+        let options = Options::default();
+        assert_eq!(filter(&report, &DigitCriterion::oxygen(), &options).unwrap().value, Bits::from(23));
+        assert_eq!(filter(&report, &DigitCriterion::c02(), &options).unwrap().value, Bits::from(10));
+    }
+
+    #[test]
+    fn test_radix_two_matches_report() {
+        let reports = [
+            // both positions tied
+            vec!["10", "01", "11", "00"],
+            // the first position constant
+            vec!["11", "11", "10"],
+            // c02 runs out of readings at bit 1
+            vec!["000", "001", "110", "111", "101"],
+            // c02 asks for a bit two identical readings don't have
+            vec!["10", "10"],
+        ];
+        for lines in reports {
+            let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+            let digits = DigitReport::parse(&lines, 2).unwrap();
+            let report = crate::report::Report::parse(&lines).unwrap();
+            for tie in [TiePolicy::PreferOne, TiePolicy::PreferZero, TiePolicy::Error] {
+                let (a, b) = (rates(&digits, tie), crate::rates(&report, tie));
+                assert_eq!(a.map(|r| (r.gamma, r.eps)), b.map(|r| (r.gamma, r.eps)), "{:?} {:?}", lines, tie);
+            }
+            let options = Options::default();
+            for (digit, bit) in [
+                (DigitCriterion::oxygen(), crate::filter::Criterion::oxygen()),
+                (DigitCriterion::c02(), crate::filter::Criterion::c02()),
+            ] {
+                assert_eq!(filter(&digits, &digit, &options), crate::filter::filter(&report, &bit, &options), "{:?}", lines);
+            }
+        }
+    }
+
+    #[test]
+    fn test_hex() {
+        let report = vec![
+            String::from("a3"),
+            String::from("a7"),
+            String::from("f7"),
+            String::from("13"),
+            String::from("1f"),
+        ];
+        let report = DigitReport::parse(&report, 16).unwrap();
+        // a and 1 tie at the first digit, 3 and 7 at the second
+        let rates = rates(&report, TiePolicy::PreferOne).unwrap();
+        assert_eq!((rates.gamma, rates.eps), (Bits::from(0xa7), Bits::from(0xff)));
+        let rates = super::rates(&report, TiePolicy::PreferZero).unwrap();
+        assert_eq!((rates.gamma, rates.eps), (Bits::from(0x13), Bits::from(0xff)));
+        assert_eq!(
+            super::rates(&report, TiePolicy::Error).err(),
+            Some(DiagnosticError::Tie { position: 0 })
+        );
+        let options = Options::default();
+        // a3 a7 then 3 and 7 tie, the higher wins
+        assert_eq!(filter(&report, &DigitCriterion::oxygen(), &options).unwrap().value, Bits::from(0xa7));
+        // f7 is the only f
+        assert_eq!(filter(&report, &DigitCriterion::c02(), &options).unwrap().value, Bits::from(0xf7));
+    }
+
+    #[test]
+    fn test_weights() {
+        // a3 three times outweighs the two f readings, and 7 and 1 tie for least common
+        let report = DigitReport::parse(&[String::from("a3 x3"), String::from("f7"), String::from("f1")], 16).unwrap();
+        let rates = rates(&report, TiePolicy::PreferOne).unwrap();
+        assert_eq!((rates.gamma, rates.eps), (Bits::from(0xa3), Bits::from(0xf1)));
+        let count = Options { duplicates: Duplicates::Count, ..Default::default() };
+        let oxygen = filter(&report, &DigitCriterion::oxygen(), &count).unwrap();
+        assert_eq!((oxygen.value, oxygen.multiplicity), (Bits::from(0xa3), 3));
+        assert_eq!(filter(&report, &DigitCriterion::c02(), &count).unwrap().value, Bits::from(0xf1));
+        assert_eq!(
+            DigitReport::parse(&[String::from("a3 3")], 16).err(),
+            Some(DiagnosticError::InvalidWeight { line: 1, found: String::from("3") })
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            DigitReport::parse(&[String::from("17"), String::from("19")], 8).err(),
+            Some(DiagnosticError::InvalidChar { line: 2, position: 1, found: '9' })
+        );
+        assert_eq!(DigitReport::parse(&sample(), 1).err(), Some(DiagnosticError::Radix(1)));
+        assert_eq!(DigitReport::parse(&sample(), 40).err(), Some(DiagnosticError::Radix(40)));
+    }
+}
@@ -0,0 +1,312 @@
+use std::ops::Range;
+use std::thread;
+
+use crate::bits::Bits;
+use crate::bitset::BitSet;
+use crate::error::DiagnosticError;
+
+/*
+readings packed by column: columns[i] holds the readings with a 1 at
+position i, counting positions from the left (most significant) end.
+counting the ones at a position within some set of readings is then a
+popcount of the column and the set.
+
+a reading can carry a repeat count, "10110 x42", and counts as that many
+copies. the counts are kept as bit planes, weights[k] holding the readings
+whose count has bit k set, so a weighted count is a popcount per plane
+shifted into place. weights is empty when every count is 1.
+*/
+#[derive(Clone, Debug)]
+pub struct Report {
+    width: usize,
+    len: usize,
+    columns: Vec<BitSet>,
+    weights: Vec<BitSet>,
+}
+
+// adds a line's repeat count to the readings so far, which every weighted count stays within
+pub fn add_weight(readings: usize, weight: usize, line: usize) -> Result<usize, DiagnosticError> {
+    readings.checked_add(weight).ok_or(DiagnosticError::Overflow { line })
+}
+
+// a line split into its reading and repeat count, 1 when there isn't one
+pub fn split_weight(line: &str, number: usize) -> Result<(&str, usize), DiagnosticError> {
+    let (reading, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let rest = rest.trim();
+    if rest.is_empty() {
+        return Ok((reading, 1));
+    }
+    match rest.strip_prefix('x').map(str::parse) {
+        Some(Ok(weight)) if weight > 0 => Ok((reading, weight)),
+        _ => Err(DiagnosticError::InvalidWeight { line: number, found: String::from(rest) }),
+    }
+}
+
+impl Report {
+    pub fn parse(lines: &[String]) -> Result<Report, DiagnosticError> {
+        let first = lines.first().ok_or(DiagnosticError::Empty)?;
+        let width = split_weight(first, 1)?.0.chars().count();
+        let mut columns = vec![BitSet::new(lines.len()); width];
+        let mut weights: Vec<usize> = Vec::with_capacity(lines.len());
+        let mut readings = 0;
+        for (i, line) in lines.iter().enumerate() {
+            let (line, weight) = split_weight(line, i + 1)?;
+            readings = add_weight(readings, weight, i + 1)?;
+            weights.push(weight);
+            let mut found = 0;
+            for (position, c) in line.chars().enumerate() {
+                if position == width {
+                    return Err(DiagnosticError::Ragged { line: i + 1, position, width });
+                }
+                match c {
+                    '1' => columns[position].insert(i),
+                    '0' => {}
+                    _ => return Err(DiagnosticError::InvalidChar { line: i + 1, position, found: c })
+                }
+                found += 1;
+            }
+            if found < width {
+                return Err(DiagnosticError::Ragged { line: i + 1, position: found, width });
+            }
+        }
+        let mut planes = vec![];
+        if weights.iter().any(|weight| *weight != 1) {
+            planes = vec![BitSet::new(lines.len()); (usize::BITS - weights.iter().max().unwrap().leading_zeros()) as usize];
+            for (i, weight) in weights.into_iter().enumerate() {
+                for (k, plane) in planes.iter_mut().enumerate() {
+                    if weight >> k & 1 == 1 {
+                        plane.insert(i);
+                    }
+                }
+            }
+        }
+        Ok(Report { width, len: lines.len(), columns, weights: planes })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    // lines in the report, however many copies each stands for
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    // how many copies of reading i the report stands for
+    pub fn weight(&self, i: usize) -> usize {
+        if self.weights.is_empty() {
+            return 1;
+        }
+        self.weights.iter().enumerate().filter(|(_, plane)| plane.contains(i)).map(|(k, _)| 1 << k).sum()
+    }
+
+    // readings in candidates, each counted weight times. parse keeps every weight adding up within a usize
+    pub fn total(&self, candidates: &BitSet) -> usize {
+        if self.weights.is_empty() {
+            return candidates.count();
+        }
+        self.weights.iter().enumerate().map(|(k, plane)| plane.count_and(candidates) << k).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn all(&self) -> BitSet {
+        BitSet::full(self.len)
+    }
+
+    pub fn column(&self, position: usize) -> &BitSet {
+        &self.columns[position]
+    }
+
+    // readings in candidates with a 1 at position, each counted weight times
+    pub fn ones(&self, position: usize, candidates: &BitSet) -> usize {
+        if self.len < PARALLEL_THRESHOLD {
+            self.ones_words(&self.columns[position], candidates, 0..candidates.word_count())
+        } else {
+            self.ones_parallel(position, candidates, threads())
+        }
+    }
+
+    pub fn ones_parallel(&self, position: usize, candidates: &BitSet, threads: usize) -> usize {
+        let column = &self.columns[position];
+        split(candidates.word_count(), threads, |words| self.ones_words(column, candidates, words))
+            .into_iter()
+            .sum()
+    }
+
+    // ones at every position among candidates
+    pub fn counts(&self, candidates: &BitSet) -> Vec<usize> {
+        if self.len < PARALLEL_THRESHOLD {
+            let words = 0..candidates.word_count();
+            self.columns.iter().map(|column| self.ones_words(column, candidates, words.clone())).collect()
+        } else {
+            self.counts_parallel(candidates, threads())
+        }
+    }
+
+    // each thread counts every position over its own run of readings
+    pub fn counts_parallel(&self, candidates: &BitSet, threads: usize) -> Vec<usize> {
+        let partials = split(candidates.word_count(), threads, |words| {
+            self.columns
+                .iter()
+                .map(|column| self.ones_words(column, candidates, words.clone()))
+                .collect::<Vec<_>>()
+        });
+        // merged in reading order, so the result never depends on which thread finished first
+        let mut counts = vec![0; self.width];
+        for partial in partials {
+            for (count, ones) in counts.iter_mut().zip(partial) {
+                *count += ones;
+            }
+        }
+        counts
+    }
+
+    fn ones_words(&self, column: &BitSet, candidates: &BitSet, words: Range<usize>) -> usize {
+        if self.weights.is_empty() {
+            return column.count_and_words(candidates, words);
+        }
+        self.weights
+            .iter()
+            .enumerate()
+            .map(|(k, plane)| column.count_and_both_words(candidates, plane, words.clone()) << k)
+            .sum()
+    }
+
+    // the same report with every bit of reading i the other way round
+    pub fn flipped(&self, i: usize) -> Report {
+        let mut flipped = self.clone();
+        for column in flipped.columns.iter_mut() {
+            if column.contains(i) {
+                column.remove(i);
+            } else {
+                column.insert(i);
+            }
+        }
+        flipped
+    }
+
+    pub fn reading(&self, i: usize) -> Bits {
+        let mut value = Bits::zero(self.width);
+        for (position, column) in self.columns.iter().enumerate() {
+            if column.contains(i) {
+                value.set(self.width - 1 - position);
+            }
+        }
+        value
+    }
+}
+
+// reports with at least this many readings are counted across threads
+pub const PARALLEL_THRESHOLD: usize = 1 << 20;
+
+fn threads() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+// runs count over words split into one run per thread, results in run order
+fn split<T: Send>(words: usize, threads: usize, count: impl Fn(Range<usize>) -> T + Sync) -> Vec<T> {
+    let chunk = words.div_ceil(threads.max(1)).max(1);
+    let count = &count;
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..words)
+            .step_by(chunk)
+            .map(|start| scope.spawn(move || count(start..(start + chunk).min(words))))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("counting thread panicked"))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let report = Report::parse(&[String::from("10110"), String::from("01111")]).unwrap();
+        assert_eq!(report.width(), 5);
+        assert_eq!(report.len(), 2);
+        assert_eq!(report.reading(0), Bits::from(22));
+        assert_eq!(report.reading(1), Bits::from(15));
+        assert_eq!(report.ones(2, &report.all()), 2);
+        assert_eq!(report.ones(0, &report.all()), 1);
+    }
+
+    #[test]
+    fn test_parallel() {
+        // 1000 readings so the last word is only partly used
+        let mut state = 3u64;
+        let lines: Vec<String> = (0..1000)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                format!("{:064b}", state)[..20].to_string()
+            })
+            .collect();
+        let report = Report::parse(&lines).unwrap();
+        let mut candidates = report.all();
+        candidates.difference(report.column(3));
+        let serial: Vec<_> = (0..20).map(|p| report.column(p).count_and(&candidates)).collect();
+        for threads in 1..=7 {
+            assert_eq!(report.counts_parallel(&candidates, threads), serial);
+            assert_eq!(report.ones_parallel(5, &candidates, threads), serial[5]);
+        }
+        assert_eq!(report.counts(&candidates), serial);
+    }
+
+    #[test]
+    fn test_weights() {
+        let report = Report::parse(&[String::from("10110 x42"), String::from("01111"), String::from("00001  x3 ")]).unwrap();
+        assert_eq!(report.len(), 3);
+        assert_eq!((report.weight(0), report.weight(1), report.weight(2)), (42, 1, 3));
+        assert_eq!(report.total(&report.all()), 46);
+        assert_eq!(report.counts(&report.all()), vec![42, 1, 43, 43, 4]);
+        for threads in 1..=3 {
+            assert_eq!(report.ones_parallel(4, &report.all(), threads), 4);
+        }
+        assert_eq!(
+            Report::parse(&[String::from("101 x0")]).err(),
+            Some(DiagnosticError::InvalidWeight { line: 1, found: String::from("x0") })
+        );
+        assert_eq!(
+            Report::parse(&[String::from("101"), String::from("011 42")]).err(),
+            Some(DiagnosticError::InvalidWeight { line: 2, found: String::from("42") })
+        );
+        // one reading can take every count there is, but nothing on top
+        let most = Report::parse(&[format!("1 x{}", usize::MAX)]).unwrap();
+        assert_eq!(most.total(&most.all()), usize::MAX);
+        assert_eq!(
+            Report::parse(&[format!("1 x{}", usize::MAX), String::from("1 x2")]).err(),
+            Some(DiagnosticError::Overflow { line: 2 })
+        );
+    }
+
+    #[test]
+    fn test_parse_wide() {
+        let line = format!("1{}1", "0".repeat(126));
+        let report = Report::parse(&[line]).unwrap();
+        assert_eq!(report.width(), 128);
+        assert_eq!(report.reading(0).to_string(), "170141183460469231731687303715884105729");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Report::parse(&[]).err(), Some(DiagnosticError::Empty));
+        assert_eq!(
+            Report::parse(&[String::from("10110"), String::from("0111")]).err(),
+            Some(DiagnosticError::Ragged { line: 2, position: 4, width: 5 })
+        );
+        assert_eq!(
+            Report::parse(&[String::from("101"), String::from("011"), String::from("1011")]).err(),
+            Some(DiagnosticError::Ragged { line: 3, position: 3, width: 3 })
+        );
+        assert_eq!(
+            Report::parse(&[String::from("101"), String::from("021")]).err(),
+            Some(DiagnosticError::InvalidChar { line: 2, position: 1, found: '2' })
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use std::fmt;
+
+use crate::bits::Bits;
+use crate::error::DiagnosticError;
+use crate::filter::{filter, filter_among, Criterion, Options, Rating};
+use crate::report::Report;
+
+// what happens to life support when one reading changes
+#[derive(Debug, PartialEq, Eq)]
+pub enum Effect {
+    Same,
+    // difference is how far it moved, in whichever direction
+    Changed { life_support: Bits, difference: Bits },
+    // oxygen or c02 no longer has a rating
+    Failed(DiagnosticError),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Impact {
+    // counting from 1
+    pub line: usize,
+    // a line with a repeat count goes with all its copies
+    pub removed: Effect,
+    // every bit of the reading the other way round
+    pub flipped: Effect,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Sensitivity {
+    pub life_support: Bits,
+    pub readings: Vec<Impact>,
+    // a minimal set of lines the rating still comes out of on its own, no line can be dropped
+    // from it, though a different set might be smaller
+    pub oxygen: Vec<usize>,
+    pub c02: Vec<usize>,
+}
+
+/*
+reruns oxygen and c02 once with each reading left out and once with it
+flipped, through the same filter the ratings come from. the lines a rating
+is decided by are what's left after greedily dropping lines for as long as
+the rating still comes out, decided at the same bit. that makes the set
+minimal, no line can be dropped from it, but not necessarily the smallest.
+with repeated readings no one line is needed in the whole report, but this
+still names a set the rating comes out of.
+*/
+pub fn analyse(report: &Report, options: &Options) -> Result<Sensitivity, DiagnosticError> {
+    let (oxygen, c02) = (Criterion::oxygen(), Criterion::c02());
+    let oxygen_rating = filter(report, &oxygen, options)?;
+    let c02_rating = filter(report, &c02, options)?;
+    let life_support = &oxygen_rating.value * &c02_rating.value;
+
+    let mut sensitivity = Sensitivity {
+        life_support,
+        readings: vec![],
+        oxygen: deciding(report, &oxygen, options, &oxygen_rating),
+        c02: deciding(report, &c02, options, &c02_rating),
+    };
+    for i in 0..report.len() {
+        let mut candidates = report.all();
+        candidates.remove(i);
+        let without = (
+            filter_among(report, &oxygen, options, candidates.clone()),
+            filter_among(report, &c02, options, candidates),
+        );
+        let flipped = report.flipped(i);
+        let flipped = (filter(&flipped, &oxygen, options), filter(&flipped, &c02, options));
+        sensitivity.readings.push(Impact {
+            line: i + 1,
+            removed: effect(without, &sensitivity.life_support),
+            flipped: effect(flipped, &sensitivity.life_support),
+        });
+    }
+    Ok(sensitivity)
+}
+
+// lines left once none can be dropped with the rating staying put, counting from 1
+fn deciding(report: &Report, criterion: &Criterion, options: &Options, baseline: &Rating) -> Vec<usize> {
+    let mut candidates = report.all();
+    // dropping a line can let another go that couldn't before, so go round until nothing does
+    let mut dropped = true;
+    while dropped {
+        dropped = false;
+        for i in 0..report.len() {
+            if !candidates.contains(i) {
+                continue;
+            }
+            candidates.remove(i);
+            let rating = filter_among(report, criterion, options, candidates.clone());
+            if rating.is_ok_and(|rating| (&rating.value, rating.position) == (&baseline.value, baseline.position)) {
+                dropped = true;
+            } else {
+                candidates.insert(i);
+            }
+        }
+    }
+    candidates.iter().map(|i| i + 1).collect()
+}
+
+fn effect(
+    (oxygen, c02): (Result<Rating, DiagnosticError>, Result<Rating, DiagnosticError>),
+    life_support: &Bits,
+) -> Effect {
+    match oxygen.and_then(|oxygen| Ok(&oxygen.value * &c02?.value)) {
+        Ok(changed) if changed == *life_support => Effect::Same,
+        Ok(changed) => Effect::Changed { difference: changed.abs_diff(life_support), life_support: changed },
+        Err(e) => Effect::Failed(e),
+    }
+}
+
+impl Sensitivity {
+    // readings where removing or flipping them changes life support
+    pub fn fragile(&self) -> impl Iterator<Item = &Impact> {
+        self.readings.iter().filter(|impact| impact.removed != Effect::Same || impact.flipped != Effect::Same)
+    }
+}
+
+impl Effect {
+    fn describe(&self, baseline: &Bits) -> String {
+        match self {
+            Effect::Same => String::from("same"),
+            Effect::Changed { life_support, difference } => {
+                let sign = if life_support > baseline { '+' } else { '-' };
+                format!("{} ({}{})", life_support, sign, difference)
+            }
+            Effect::Failed(e) => format!("fails, {}", e),
+        }
+    }
+}
+
+impl fmt::Display for Sensitivity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "life support: {}", self.life_support)?;
+        writeln!(f)?;
+        writeln!(f, "{:>6}  {:<32}  flipped", "line", "removed")?;
+        for impact in &self.readings {
+            let removed = impact.removed.describe(&self.life_support);
+            writeln!(f, "{:>6}  {:<32}  {}", impact.line, removed, impact.flipped.describe(&self.life_support))?;
+        }
+        writeln!(f)?;
+        let lines = |lines: &[usize]| lines.iter().map(|line| line.to_string()).collect::<Vec<_>>().join(", ");
+        writeln!(f, "oxygen decided by lines {}", lines(&self.oxygen))?;
+        writeln!(f, "c02 decided by lines {}", lines(&self.c02))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample() {
+        let report = vec![
+            String::from("00100"),
+            String::from("11110"),
+            String::from("10110"),
+            String::from("10111"),
+            String::from("10101"),
+            String::from("01111"),
+            String::from("00111"),
+            String::from("11100"),
+            String::from("10000"),
+            String::from("11001"),
+            String::from("00010"),
+            String::from("01010"),
+        ];
+        let report = Report::parse(&report).unwrap();
+        let sensitivity = analyse(&report, &Options::default()).unwrap();
+        assert_eq!(sensitivity.life_support, Bits::from(230));
+        assert_eq!(sensitivity.readings.len(), 12);
+        // 10110 and 10111 tie at bit 4 on their own, but c02 needs every 0 at bit 0 to get that far
+        assert_eq!(sensitivity.oxygen, vec![3, 4]);
+        assert_eq!(sensitivity.c02, vec![1, 4, 5, 6, 7, 8, 9, 10, 11, 12]);
+        assert_eq!(sensitivity.readings[1], Impact { line: 2, removed: Effect::Same, flipped: Effect::Same });
+        // without 10110 oxygen goes 10111 to 11110 and life support 230 to 300
+        assert_eq!(
+            sensitivity.readings[2].removed,
+            Effect::Changed { life_support: Bits::from(300), difference: Bits::from(70) }
+        );
+        // without 00010 the two left for c02 both lack the least common bit
+        assert_eq!(sensitivity.readings[10].removed, Effect::Failed(DiagnosticError::Exhausted { position: 2 }));
+        assert_eq!(sensitivity.fragile().count(), 8);
+    }
+
+    #[test]
+    fn test_repeats() {
+        let report = vec![String::from("0110 x3"), String::from("1001 x4"), String::from("1111")];
+        let sensitivity = analyse(&Report::parse(&report).unwrap(), &Options::default()).unwrap();
+        assert_eq!(sensitivity.oxygen, vec![2]);
+        assert_eq!(sensitivity.c02, vec![1, 2]);
+        // written out one per line no one line changes either rating, yet each still has lines it comes out of
+        let lines: Vec<String> = ["0110", "0110", "0110", "1001", "1001", "1001", "1001", "1111"].iter().map(|s| s.to_string()).collect();
+        let sensitivity = analyse(&Report::parse(&lines).unwrap(), &Options::default()).unwrap();
+        assert!(sensitivity.readings.iter().all(|impact| impact.removed == Effect::Same));
+        assert_eq!(sensitivity.oxygen, vec![6, 7]);
+        assert_eq!(sensitivity.c02, vec![3, 8]);
+    }
+}
@@ -0,0 +1,129 @@
+use std::io::BufRead;
+
+use crate::bits::Bits;
+use crate::error::DiagnosticError;
+use crate::report::{add_weight, split_weight};
+use crate::{Rates, TiePolicy};
+
+/*
+gamma and epsilon only need the ones at each position, so they can be
+counted a line at a time straight off a reader. memory stays at one line
+plus one count per position however long the report runs.
+*/
+pub fn rates<R: BufRead>(mut reader: R, tie: TiePolicy) -> Result<Rates, DiagnosticError> {
+    let mut ones: Vec<usize> = vec![];
+    let mut lines = 0;
+    // readings, counting every repeat
+    let mut total = 0;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let read = reader
+            .read_line(&mut line)
+            .map_err(|e| DiagnosticError::Io(format!("could not read line {}: {}", lines + 1, e)))?;
+        if read == 0 {
+            break;
+        }
+        let reading = line.strip_suffix('\n').unwrap_or(&line);
+        let reading = reading.strip_suffix('\r').unwrap_or(reading);
+        lines += 1;
+        let (reading, weight) = split_weight(reading, lines)?;
+        if lines == 1 {
+            ones = vec![0; reading.chars().count()];
+        }
+        total = add_weight(total, weight, lines)?;
+        count(reading, weight, &mut ones, lines)?;
+    }
+    if lines == 0 {
+        return Err(DiagnosticError::Empty);
+    }
+
+    let width = ones.len();
+    let mut g = Bits::zero(width);
+    for (i, ones) in ones.into_iter().enumerate() {
+        if tie.majority(ones, total, i)? == 1 {
+            g.set(width - 1 - i);
+        }
+    }
+    Ok(Rates { eps: g.not(), gamma: g })
+}
+
+// adds weight copies of a reading to the counts, the width is set by the first line
+fn count(reading: &str, weight: usize, ones: &mut [usize], line: usize) -> Result<(), DiagnosticError> {
+    let width = ones.len();
+    let mut found = 0;
+    for (position, c) in reading.chars().enumerate() {
+        if position == width {
+            return Err(DiagnosticError::Ragged { line, position, width });
+        }
+        match c {
+            '1' => ones[position] += weight,
+            '0' => {}
+            _ => return Err(DiagnosticError::InvalidChar { line, position, found: c }),
+        }
+        found += 1;
+    }
+    if found < width {
+        return Err(DiagnosticError::Ragged { line, position: found, width });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::Report;
+
+    const SAMPLE: &str = "00100\n11110\n10110\n10111\n10101\n01111\n00111\n11100\n10000\n11001\n00010\n01010\n";
+
+    #[test]
+    fn test_stream() {
+        let rates = rates(SAMPLE.as_bytes(), TiePolicy::PreferOne).unwrap();
+        assert_eq!((rates.gamma, rates.eps), (Bits::from(22), Bits::from(9)));
+        // same again with windows line endings and no final newline
+        let crlf = SAMPLE.trim_end().replace('\n', "\r\n");
+        let rates = super::rates(crlf.as_bytes(), TiePolicy::PreferOne).unwrap();
+        assert_eq!((rates.gamma, rates.eps), (Bits::from(22), Bits::from(9)));
+    }
+
+    #[test]
+    fn test_stream_matches_report() {
+        let lines: Vec<String> = SAMPLE.lines().map(String::from).collect();
+        let report = Report::parse(&lines).unwrap();
+        for tie in [TiePolicy::PreferOne, TiePolicy::PreferZero] {
+            let streamed = rates(SAMPLE.as_bytes(), tie).unwrap();
+            let loaded = crate::rates(&report, tie).unwrap();
+            assert_eq!((streamed.gamma, streamed.eps), (loaded.gamma, loaded.eps));
+        }
+    }
+
+    #[test]
+    fn test_stream_weights() {
+        // 01 four times outweighs 10 three times at both positions
+        let rates = rates("10 x3\n01 x4\n".as_bytes(), TiePolicy::Error).unwrap();
+        assert_eq!((rates.gamma, rates.eps), (Bits::from(0b01), Bits::from(0b10)));
+    }
+
+    #[test]
+    fn test_stream_errors() {
+        assert_eq!(rates("".as_bytes(), TiePolicy::PreferOne).err(), Some(DiagnosticError::Empty));
+        assert_eq!(
+            rates("101\n10\n".as_bytes(), TiePolicy::PreferOne).err(),
+            Some(DiagnosticError::Ragged { line: 2, position: 2, width: 3 })
+        );
+        assert_eq!(
+            rates("101\n1011\n".as_bytes(), TiePolicy::PreferOne).err(),
+            Some(DiagnosticError::Ragged { line: 2, position: 3, width: 3 })
+        );
+        assert_eq!(
+            rates("101\n1x1\n".as_bytes(), TiePolicy::PreferOne).err(),
+            Some(DiagnosticError::InvalidChar { line: 2, position: 1, found: 'x' })
+        );
+        assert_eq!(
+            rates("10\n01\n".as_bytes(), TiePolicy::Error).err(),
+            Some(DiagnosticError::Tie { position: 0 })
+        );
+        let huge = format!("1 x{}\n1 x2\n", usize::MAX);
+        assert_eq!(rates(huge.as_bytes(), TiePolicy::PreferOne).err(), Some(DiagnosticError::Overflow { line: 2 }));
+    }
+}
@@ -0,0 +1,155 @@
+use crate::error::DiagnosticError;
+use crate::filter::{run, Criterion, Options, Rating};
+use crate::report::Report;
+
+// one position of the filter
+#[derive(Debug, PartialEq, Eq)]
+pub struct Step {
+    pub position: usize,
+    pub ones: usize,
+    pub zeros: usize,
+    // the bit the criterion chose
+    pub keep: u32,
+    // candidates left after this step
+    pub kept: usize,
+    // report lines dropped at this step, counting from 1
+    pub dropped: Vec<usize>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Trace {
+    pub steps: Vec<Step>,
+    pub result: Result<Rating, DiagnosticError>,
+}
+
+// runs the same filter as oxygen and c02 do, recording each step on the way
+pub fn trace(report: &Report, criterion: &Criterion, options: &Options) -> Trace {
+    let mut steps = vec![];
+    let result = run(report, criterion, options, report.all(), Some(&mut steps));
+    Trace { steps, result }
+}
+
+impl Trace {
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for step in &self.steps {
+            let dropped: Vec<_> = step.dropped.iter().map(|line| line.to_string()).collect();
+            text.push_str(&format!(
+                "bit {}: {} ones {} zeros, keep {}, {} left, dropped lines {}\n",
+                step.position,
+                step.ones,
+                step.zeros,
+                step.keep,
+                step.kept,
+                if dropped.is_empty() { String::from("none") } else { dropped.join(" ") }
+            ));
+        }
+        match &self.result {
+            Ok(rating) => {
+                text.push_str(&format!("rating {} ({:b})", rating.value, rating.value));
+                if let Some(position) = rating.position {
+                    text.push_str(&format!(" decided at bit {}", position));
+                }
+                if rating.multiplicity > 1 {
+                    text.push_str(&format!(", {} readings", rating.multiplicity));
+                }
+                text.push('\n');
+            }
+            Err(e) => text.push_str(&format!("error: {}\n", e)),
+        }
+        text
+    }
+
+    pub fn json(&self) -> String {
+        let steps: Vec<_> = self
+            .steps
+            .iter()
+            .map(|step| {
+                let dropped: Vec<_> = step.dropped.iter().map(|line| line.to_string()).collect();
+                format!(
+                    r#"{{"position":{},"ones":{},"zeros":{},"keep":{},"kept":{},"dropped":[{}]}}"#,
+                    step.position,
+                    step.ones,
+                    step.zeros,
+                    step.keep,
+                    step.kept,
+                    dropped.join(",")
+                )
+            })
+            .collect();
+        let result = match &self.result {
+            Ok(rating) => format!(
+                r#"{{"value":"{}","binary":"{:b}","position":{},"multiplicity":{}}}"#,
+                rating.value,
+                rating.value,
+                rating.position.map_or(String::from("null"), |p| p.to_string()),
+                rating.multiplicity
+            ),
+            Err(e) => format!(r#"{{"error":"{}"}}"#, escape(&e.to_string())),
+        };
+        format!(r#"{{"steps":[{}],"result":{}}}"#, steps.join(","), result)
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.chars()
+        .flat_map(|c| match c {
+            '"' | '\\' => vec!['\\', c],
+            _ => vec![c],
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bits::Bits;
+
+    #[test]
+    fn test_trace() {
+        let report = vec![
+            String::from("00100"),
+            String::from("11110"),
+            String::from("10110"),
+            String::from("10111"),
+            String::from("10101"),
+            String::from("01111"),
+            String::from("00111"),
+            String::from("11100"),
+            String::from("10000"),
+            String::from("11001"),
+            String::from("00010"),
+            String::from("01010"),
+        ];
+        let report = Report::parse(&report).unwrap();
+        let trace = trace(&report, &Criterion::c02(), &Options::default());
+        assert_eq!(trace.steps, vec![
+            Step { position: 0, ones: 7, zeros: 5, keep: 0, kept: 5, dropped: vec![2, 3, 4, 5, 8, 9, 10] },
+            Step { position: 1, ones: 2, zeros: 3, keep: 1, kept: 2, dropped: vec![1, 7, 11] },
+            Step { position: 2, ones: 1, zeros: 1, keep: 0, kept: 1, dropped: vec![6] },
+        ]);
+        assert_eq!(trace.result.as_ref().unwrap().value, Bits::from(10));
+        assert_eq!(
+            trace.text().lines().last(),
+            Some("rating 10 (01010) decided at bit 2")
+        );
+        assert!(trace.json().starts_with(r#"{"steps":[{"position":0,"ones":7,"zeros":5,"keep":0,"kept":5,"dropped":[2,3,4,5,8,9,10]},"#));
+        assert!(trace.json().ends_with(r#""result":{"value":"10","binary":"01010","position":2,"multiplicity":1}}"#));
+    }
+
+    #[test]
+    fn test_trace_exhausted() {
+        let report = vec![
+            String::from("000"),
+            String::from("001"),
+            String::from("110"),
+            String::from("111"),
+            String::from("101"),
+        ];
+        let report = Report::parse(&report).unwrap();
+        let trace = trace(&report, &Criterion::c02(), &Options::default());
+        assert_eq!(trace.steps.last(), Some(&Step { position: 1, ones: 0, zeros: 2, keep: 1, kept: 0, dropped: vec![1, 2] }));
+        assert_eq!(trace.result, Err(DiagnosticError::Exhausted { position: 1 }));
+        assert!(trace.json().ends_with(r#""result":{"error":"bit 1: no readings left"}}"#));
+    }
+}
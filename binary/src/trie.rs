@@ -0,0 +1,217 @@
+use crate::bits::Bits;
+use crate::filter::{Counts, Criterion, Options, Rating};
+use crate::report::Report;
+use crate::error::DiagnosticError;
+use crate::layout::Layout;
+
+// children index into Trie::nodes, 0 is the root so it doubles as "no child"
+#[derive(Clone, Copy, Default)]
+struct Node {
+    count: usize,
+    children: [u32; 2],
+    // first reading that went through this node
+    first: u32,
+}
+
+/*
+every reading inserted bit by bit from the left, with each node counting
+the readings below it. the counts at a node are exactly what the filter
+would count among its candidates, so a rating is one walk from the root.
+*/
+pub struct Trie {
+    width: usize,
+    // the report positions each level of the trie stands for
+    positions: Vec<usize>,
+    nodes: Vec<Node>,
+}
+
+impl Trie {
+    pub fn build(report: &Report) -> Trie {
+        Trie::from_positions(report, (0..report.width()).collect())
+    }
+
+    // levels follow the layout's order, leaving out masked positions
+    pub fn with_layout(report: &Report, layout: &Layout) -> Result<Trie, DiagnosticError> {
+        Ok(Trie::from_positions(report, layout.positions(report.width())?))
+    }
+
+    fn from_positions(report: &Report, positions: Vec<usize>) -> Trie {
+        let mut trie = Trie { width: report.width(), positions, nodes: vec![Node::default()] };
+        for i in 0..report.len() {
+            let mut node = 0;
+            let weight = report.weight(i);
+            trie.nodes[0].count += weight;
+            for &position in &trie.positions {
+                let bit = report.column(position).contains(i) as usize;
+                if trie.nodes[node].children[bit] == 0 {
+                    trie.nodes.push(Node { first: i as u32, ..Default::default() });
+                    trie.nodes[node].children[bit] = (trie.nodes.len() - 1) as u32;
+                }
+                node = trie.nodes[node].children[bit] as usize;
+                trie.nodes[node].count += weight;
+            }
+        }
+        trie
+    }
+
+    fn count(&self, child: u32) -> usize {
+        if child == 0 {
+            0
+        } else {
+            self.nodes[child as usize].count
+        }
+    }
+
+    // whether everything below node is one reading, however many times over
+    fn single_path(&self, mut node: usize) -> bool {
+        loop {
+            match self.nodes[node].children {
+                [0, 0] => return true,
+                [child, 0] | [0, child] => node = child as usize,
+                _ => return false,
+            }
+        }
+    }
+
+    // the layout is the one the trie was built with, options only give the duplicates policy
+    pub fn rating(&self, criterion: &Criterion, options: &Options) -> Result<Rating, DiagnosticError> {
+        let mut value = Bits::zero(self.width);
+        let mut decided = None;
+        let mut node = 0;
+        // set once the readings left are all the same
+        let mut settled = false;
+        for &position in &self.positions {
+            let [zero, one] = self.nodes[node].children;
+            let mut bit = (one != 0) as u32;
+            if !settled && self.nodes[node].count > 1 {
+                let counts = Counts { position, ones: self.count(one), zeros: self.count(zero) };
+                let keep = criterion.keep(counts)?;
+                if self.nodes[node].children[keep as usize] != 0 {
+                    bit = keep;
+                    decided = Some(position);
+                } else if self.single_path(node) {
+                    settled = true;
+                } else {
+                    return Err(DiagnosticError::Exhausted { position });
+                }
+            }
+            if bit == 1 {
+                value.set(self.width - 1 - position);
+            }
+            node = self.nodes[node].children[bit as usize] as usize;
+        }
+        let leaf = self.nodes[node];
+        options.duplicates.resolve(value, decided, leaf.count, leaf.first as usize + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::{filter, Duplicates};
+
+    // xorshift, enough to shuffle up some readings
+    fn readings(seed: u64, width: usize, len: usize) -> Vec<String> {
+        let mut state = seed;
+        let mut lines = std::collections::BTreeSet::new();
+        while lines.len() < len {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            lines.insert(format!("{:064b}", state)[..width].to_string());
+        }
+        lines.into_iter().collect()
+    }
+
+    #[test]
+    fn test_repeats() {
+        // each reading three times over
+        let mut lines = readings(5, 10, 200);
+        lines = lines.iter().flat_map(|line| vec![line.clone(); 3]).collect();
+        let report = Report::parse(&lines).unwrap();
+        let trie = Trie::build(&report);
+        for duplicates in [Duplicates::Merge, Duplicates::Count, Duplicates::Error] {
+            let options = Options { duplicates, ..Default::default() };
+            for criterion in [Criterion::oxygen(), Criterion::c02()] {
+                assert_eq!(trie.rating(&criterion, &options), filter(&report, &criterion, &options));
+            }
+        }
+        let count = Options { duplicates: Duplicates::Count, ..Default::default() };
+        assert_eq!(trie.rating(&Criterion::oxygen(), &count).unwrap().multiplicity, 3);
+    }
+
+    #[test]
+    fn test_sample() {
+        let report = vec![
+            String::from("00100"),
+            String::from("11110"),
+            String::from("10110"),
+            String::from("10111"),
+            String::from("10101"),
+            String::from("01111"),
+            String::from("00111"),
+            String::from("11100"),
+            String::from("10000"),
+            String::from("11001"),
+            String::from("00010"),
+            String::from("01010"),
+        ];
+        let report = Report::parse(&report).unwrap();
+        let trie = Trie::build(&report);
+        let options = Options::default();
+        for criterion in [Criterion::oxygen(), Criterion::c02()] {
+            assert_eq!(trie.rating(&criterion, &options).unwrap(), filter(&report, &criterion, &options).unwrap());
+        }
+        // always keeping the 1s narrows down to 11110 by bit 3
+        let ones = Criterion::Custom(Box::new(|_| true));
+        assert_eq!(trie.rating(&ones, &options), filter(&report, &ones, &options));
+        assert_eq!(trie.rating(&ones, &options).unwrap().value, Bits::from(0b11110));
+    }
+
+    #[test]
+    fn test_against_filter() {
+        for (seed, width, len) in [(1, 12, 1000), (7, 20, 300), (42, 64, 50), (99, 3, 8)] {
+            let report = Report::parse(&readings(seed, width, len)).unwrap();
+            let trie = Trie::build(&report);
+            let options = Options::default();
+            assert_eq!(trie.rating(&Criterion::oxygen(), &options), filter(&report, &Criterion::oxygen(), &options));
+            // least common can run out of readings, which both should agree on too
+            assert_eq!(trie.rating(&Criterion::c02(), &options), filter(&report, &Criterion::c02(), &options));
+        }
+    }
+
+    #[test]
+    fn test_weights() {
+        // the same readings with repeat counts and written out in full
+        let lines = readings(11, 10, 300);
+        let mut weighted = vec![];
+        let mut expanded = vec![];
+        for (i, line) in lines.iter().enumerate() {
+            let weight = i % 7 + 1;
+            weighted.push(format!("{} x{}", line, weight));
+            expanded.extend(vec![line.clone(); weight]);
+        }
+        let weighted = Report::parse(&weighted).unwrap();
+        let expanded = Report::parse(&expanded).unwrap();
+        let trie = Trie::build(&weighted);
+        let count = Options { duplicates: Duplicates::Count, ..Default::default() };
+        for criterion in [Criterion::oxygen(), Criterion::c02()] {
+            let rating = filter(&weighted, &criterion, &count);
+            assert_eq!(trie.rating(&criterion, &count), rating);
+            let full = filter(&expanded, &criterion, &count);
+            assert_eq!(rating.map(|r| (r.value, r.multiplicity)), full.map(|r| (r.value, r.multiplicity)));
+        }
+        assert_eq!(crate::counts(&weighted), crate::counts(&expanded));
+    }
+
+    #[test]
+    fn test_layout() {
+        let report = Report::parse(&readings(3, 16, 500)).unwrap();
+        let layout = Layout { order: "lsb".parse().unwrap(), mask: Some("1110111111011111".parse().unwrap()) };
+        let trie = Trie::with_layout(&report, &layout).unwrap();
+        let options = Options { layout, ..Default::default() };
+        for criterion in [Criterion::oxygen(), Criterion::c02()] {
+            assert_eq!(trie.rating(&criterion, &options), filter(&report, &criterion, &options));
+        }
+    }
+}
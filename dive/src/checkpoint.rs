@@ -0,0 +1,80 @@
+use std::str::FromStr;
+
+use crate::Submarine;
+
+/*
+an assertion line in a command file, checked against the sub as it is after
+the commands above it. any of the fields can be left out:
+expect x=15 depth=60 aim=10
+*/
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub x: Option<i32>,
+    pub depth: Option<i32>,
+    pub aim: Option<i32>,
+}
+
+pub fn is_checkpoint(step: &str) -> bool {
+    step.split_whitespace().next() == Some("expect")
+}
+
+impl FromStr for Checkpoint {
+    type Err = String;
+
+    fn from_str(step: &str) -> Result<Checkpoint, String> {
+        let mut parts = step.split_whitespace();
+        if parts.next() != Some("expect") {
+            return Err(format!("invalid checkpoint {}", step));
+        }
+        let mut checkpoint = Checkpoint::default();
+        for part in parts {
+            let (key, value) = part.split_once('=').ok_or(format!("expected key=value, got {}", part))?;
+            let value = Some(value.parse::<i32>().map_err(|_| format!("invalid value {}", part))?);
+            match key {
+                "x" => checkpoint.x = value,
+                "depth" => checkpoint.depth = value,
+                "aim" => checkpoint.aim = value,
+                _ => return Err(format!("invalid field {}", key)),
+            }
+        }
+        Ok(checkpoint)
+    }
+}
+
+impl Checkpoint {
+    // lists every expected field against the actual value, marking mismatches
+    pub fn check(&self, sub: &Submarine) -> Result<(), String> {
+        let fields = [("x", self.x, sub.x), ("depth", self.depth, sub.depth), ("aim", self.aim, sub.aim)];
+        let mut failed = false;
+        let mut diff = String::new();
+        for (name, expected, actual) in fields {
+            if let Some(expected) = expected {
+                let marker = if expected == actual { "" } else { "  <- mismatch" };
+                failed |= expected != actual;
+                diff.push_str(&format!("\n  {:<6} expected {:<8} got {}{}", name, expected, actual, marker));
+            }
+        }
+        if failed {
+            Err(diff)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let checkpoint = "expect depth=60 aim=10".parse::<Checkpoint>().unwrap();
+        assert_eq!(checkpoint, Checkpoint { x: None, depth: Some(60), aim: Some(10) });
+        assert_eq!(checkpoint.check(&Submarine { x: 15, depth: 60, aim: 10 }), Ok(()));
+        assert_eq!(
+            checkpoint.check(&Submarine { x: 15, depth: 50, aim: 10 }),
+            Err(String::from("\n  depth  expected 60       got 50  <- mismatch\n  aim    expected 10       got 10"))
+        );
+        assert!("expect speed=3".parse::<Checkpoint>().is_err());
+    }
+}
@@ -0,0 +1,117 @@
+use std::fmt;
+
+use crate::{Command, Submarine};
+
+// a step where the sub went below the seafloor
+#[derive(Debug, PartialEq, Eq)]
+pub struct Collision {
+    pub step: usize,
+    pub x: i32,
+    pub depth: i32,
+    pub floor: i32,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CollisionReport {
+    pub collisions: Vec<Collision>,
+    // smallest floor - depth seen, negative when the sub went through the floor
+    pub min_clearance: Option<i32>,
+}
+
+/*
+floor is a sonar depth profile indexed by horizontal position, so floor[x]
+is the seafloor depth under the sub at x. a forward leg is checked at every
+x it passes, with the depth changing by aim at each one, and up and down
+are checked where the sub stands. positions outside the profile are not
+checked.
+*/
+pub fn collisions(commands: &[Command], floor: &[i32]) -> CollisionReport {
+    let mut sub = Submarine::default();
+    let mut report = CollisionReport { collisions: vec![], min_clearance: None };
+
+    for (i, command) in commands.iter().enumerate() {
+        let start = sub;
+        sub.apply(*command);
+        let passed: Vec<(i32, i32)> = match command {
+            Command::Forward(distance) => {
+                let step = distance.signum();
+                (1..=distance.abs()).map(|k| (start.x + step * k, start.depth + start.aim * step * k)).collect()
+            }
+            _ => vec![(sub.x, sub.depth)],
+        };
+        for (x, depth) in passed {
+            let floor_depth = match usize::try_from(x).ok().and_then(|x| floor.get(x)) {
+                Some(depth) => *depth,
+                None => continue,
+            };
+            let clearance = floor_depth - depth;
+            report.min_clearance = Some(report.min_clearance.map_or(clearance, |c| c.min(clearance)));
+            if depth > floor_depth {
+                report.collisions.push(Collision { step: i + 1, x, depth, floor: floor_depth });
+            }
+        }
+    }
+    report
+}
+
+impl fmt::Display for CollisionReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for c in &self.collisions {
+            writeln!(f, "step {} x {} depth {} floor {}", c.step, c.x, c.depth, c.floor)?;
+        }
+        writeln!(f, "collisions {}", self.collisions.len())?;
+        match self.min_clearance {
+            Some(clearance) => writeln!(f, "min clearance {}", clearance),
+            None => writeln!(f, "min clearance n/a"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collisions() {
+        let commands = vec![
+            Command::Forward(5),
+            Command::Down(5),
+            Command::Forward(8),
+            Command::Up(3),
+            Command::Down(8),
+            Command::Forward(2)
+        ];
+        let mut floor = vec![50; 16];
+        floor[15] = 55;
+        let report = collisions(&commands, &floor);
+        // x 5 depth 0, x 13 depth 40, x 15 depth 60
+        assert_eq!(report.collisions, vec![Collision { step: 6, x: 15, depth: 60, floor: 55 }]);
+        assert_eq!(report.min_clearance, Some(-5));
+    }
+
+    #[test]
+    fn test_ridge_mid_leg() {
+        // aim 2 over a 6 long leg, ending well clear of the floor at x 6 but through a ridge at x 3
+        let commands = vec![Command::Down(2), Command::Forward(6)];
+        let mut floor = vec![20; 7];
+        floor[3] = 5;
+        let report = collisions(&commands, &floor);
+        assert_eq!(report.collisions, vec![Collision { step: 2, x: 3, depth: 6, floor: 5 }]);
+        assert_eq!(report.min_clearance, Some(-1));
+    }
+
+    #[test]
+    fn test_collisions_outside_profile() {
+        let commands = vec![Command::Down(100), Command::Forward(20)];
+        let report = collisions(&commands, &[1, 2, 3]);
+        // the leg leaves the profile after x 2, so only x 0, 1 and 2 are checked
+        assert_eq!(
+            report.collisions,
+            vec![
+                Collision { step: 2, x: 1, depth: 100, floor: 2 },
+                Collision { step: 2, x: 2, depth: 200, floor: 3 },
+            ]
+        );
+        assert_eq!(report.min_clearance, Some(-197));
+    }
+}
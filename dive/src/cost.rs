@@ -0,0 +1,190 @@
+use std::fmt;
+
+use crate::{Command, Submarine};
+
+/*
+cost of a single leg, worked out from the state before the command runs:
+base + distance * (distance_rate + aim_rate * |aim| + depth_rate * depth)
+*/
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rate {
+    pub base: i64,
+    pub distance: i64,
+    pub aim: i64,
+    pub depth: i64,
+}
+
+impl Rate {
+    fn cost(&self, distance: i32, sub: &Submarine) -> i64 {
+        let distance = i64::from(distance).abs();
+        let aim = i64::from(sub.aim).abs();
+        let depth = i64::from(sub.depth).max(0);
+        self.base + distance * (self.distance + self.aim * aim + self.depth * depth)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CommandCost {
+    pub fuel: Rate,
+    pub time: Rate,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CostModel {
+    pub forward: CommandCost,
+    pub up: CommandCost,
+    pub down: CommandCost,
+}
+
+// one unit of fuel and time per unit of distance for every command
+impl Default for CostModel {
+    fn default() -> CostModel {
+        let unit = Rate { distance: 1, ..Default::default() };
+        let cost = CommandCost { fuel: unit, time: unit };
+        CostModel { forward: cost, up: cost, down: cost }
+    }
+}
+
+impl CostModel {
+    /*
+    one rate per line, missing keys are 0 and blank or # lines are skipped:
+    forward fuel base=2 distance=1 aim=1
+    down time distance=3
+    */
+    pub fn from_lines(lines: &[String]) -> CostModel {
+        let mut model = CostModel::default();
+        for line in lines {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let command = match parts.next().unwrap() {
+                "forward" => &mut model.forward,
+                "up" => &mut model.up,
+                "down" => &mut model.down,
+                direction => panic!("invalid direction {}", direction)
+            };
+            let rate = match parts.next().expect("missing cost kind") {
+                "fuel" => &mut command.fuel,
+                "time" => &mut command.time,
+                kind => panic!("invalid cost kind {}", kind)
+            };
+            *rate = Rate::default();
+            for part in parts {
+                let (key, value) = part.split_once('=').expect("expected key=value");
+                let value = value.parse::<i64>().expect("invalid rate");
+                match key {
+                    "base" => rate.base = value,
+                    "distance" => rate.distance = value,
+                    "aim" => rate.aim = value,
+                    "depth" => rate.depth = value,
+                    _ => panic!("invalid rate {}", key)
+                }
+            }
+        }
+        model
+    }
+
+    fn costs(&self, command: Command) -> (&CommandCost, i32) {
+        match command {
+            Command::Forward(distance) => (&self.forward, distance),
+            Command::Up(distance) => (&self.up, distance),
+            Command::Down(distance) => (&self.down, distance),
+        }
+    }
+
+    // what command costs starting from sub, before it moves
+    pub fn leg(&self, command: Command, sub: &Submarine) -> Leg {
+        let (cost, distance) = self.costs(command);
+        Leg { command, fuel: cost.fuel.cost(distance, sub), time: cost.time.cost(distance, sub) }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Leg {
+    pub command: Command,
+    pub fuel: i64,
+    pub time: i64,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Route {
+    pub sub: Submarine,
+    pub legs: Vec<Leg>,
+    pub fuel: i64,
+    pub time: i64,
+}
+
+impl Route {
+    pub fn position(&self) -> i64 {
+        i64::from(self.sub.x) * i64::from(self.sub.depth)
+    }
+
+    pub fn push(&mut self, leg: Leg) {
+        self.fuel += leg.fuel;
+        self.time += leg.time;
+        self.sub.apply(leg.command);
+        self.legs.push(leg);
+    }
+}
+
+pub fn route(commands: &[Command], model: &CostModel) -> Route {
+    let mut route = Route::default();
+    for command in commands {
+        route.push(model.leg(*command, &route.sub));
+    }
+    route
+}
+
+impl fmt::Display for Route {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for leg in &self.legs {
+            writeln!(f, "{} fuel {} time {}", leg.command, leg.fuel, leg.time)?;
+        }
+        writeln!(f, "x {} depth {} aim {}", self.sub.x, self.sub.depth, self.sub.aim)?;
+        writeln!(f, "position {}", self.position())?;
+        writeln!(f, "fuel {}", self.fuel)?;
+        writeln!(f, "time {}", self.time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands() -> Vec<Command> {
+        vec![
+            Command::Forward(5),
+            Command::Down(5),
+            Command::Forward(8),
+            Command::Up(3),
+            Command::Down(8),
+            Command::Forward(2)
+        ]
+    }
+
+    #[test]
+    fn test_default_route() {
+        let route = route(&commands(), &CostModel::default());
+        assert_eq!(route.position(), 900);
+        assert_eq!(route.fuel, 31);
+        assert_eq!(route.time, 31);
+    }
+
+    #[test]
+    fn test_configured_route() {
+        let model = CostModel::from_lines(&[
+            String::from("# forwards burn more the steeper the dive"),
+            String::from("forward fuel base=1 distance=1 aim=1"),
+            String::from("up time base=10"),
+        ]);
+        let route = route(&commands(), &model);
+        // forward 5 at aim 0, forward 8 at aim 5, forward 2 at aim 10
+        let fuel: Vec<_> = route.legs.iter().map(|leg| leg.fuel).collect();
+        assert_eq!(fuel, vec![6, 5, 49, 3, 8, 23]);
+        assert_eq!(route.fuel, 94);
+        // up costs 10 regardless of distance, the rest keep the default
+        assert_eq!(route.time, 5 + 5 + 8 + 10 + 8 + 2);
+    }
+}
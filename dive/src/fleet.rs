@@ -0,0 +1,144 @@
+use std::fmt;
+use std::thread;
+
+use crate::{collision, Command, Submarine};
+
+#[derive(Debug)]
+pub struct Sim {
+    pub name: String,
+    // state after each step
+    pub track: Vec<Submarine>,
+    // collisions with the seafloor, when a floor profile was given
+    pub violations: Option<usize>,
+}
+
+impl Sim {
+    pub fn last(&self) -> Submarine {
+        self.track.last().copied().unwrap_or_default()
+    }
+}
+
+#[derive(Debug)]
+pub struct Fleet {
+    pub sims: Vec<Sim>,
+}
+
+// two subs within range of each other after the same step
+#[derive(Debug, PartialEq)]
+pub struct Encounter {
+    pub step: usize,
+    pub a: usize,
+    pub b: usize,
+    pub distance: f64,
+}
+
+// every route runs on its own thread, results come back in route order
+pub fn run(routes: &[(String, Vec<Command>)], floor: Option<&[i32]>) -> Fleet {
+    let sims = thread::scope(|scope| {
+        let handles: Vec<_> = routes
+            .iter()
+            .map(|(name, commands)| scope.spawn(move || simulate(name, commands, floor)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("simulation panicked"))
+            .collect()
+    });
+    Fleet { sims }
+}
+
+fn simulate(name: &str, commands: &[Command], floor: Option<&[i32]>) -> Sim {
+    let mut sub = Submarine::default();
+    let track = commands
+        .iter()
+        .map(|command| {
+            sub.apply(*command);
+            sub
+        })
+        .collect();
+    Sim {
+        name: name.into(),
+        track,
+        violations: floor.map(|floor| collision::collisions(commands, floor).collisions.len()),
+    }
+}
+
+impl Fleet {
+    // distance is measured in the x/depth plane
+    pub fn proximity(&self, within: f64) -> Vec<Encounter> {
+        let steps = self.sims.iter().map(|sim| sim.track.len()).max().unwrap_or(0);
+        let mut encounters = vec![];
+        for step in 0..steps {
+            for (a, sim_a) in self.sims.iter().enumerate() {
+                for (b, sim_b) in self.sims.iter().enumerate().skip(a + 1) {
+                    let (sub_a, sub_b) = match (sim_a.track.get(step), sim_b.track.get(step)) {
+                        (Some(sub_a), Some(sub_b)) => (sub_a, sub_b),
+                        _ => continue,
+                    };
+                    let dx = f64::from(sub_a.x - sub_b.x);
+                    let dd = f64::from(sub_a.depth - sub_b.depth);
+                    let distance = dx.hypot(dd);
+                    if distance <= within {
+                        encounters.push(Encounter { step: step + 1, a, b, distance });
+                    }
+                }
+            }
+        }
+        encounters
+    }
+}
+
+impl fmt::Display for Fleet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:<20} {:>8} {:>10} {:>8} {:>14} {:>10}", "route", "x", "depth", "aim", "product", "violations")?;
+        for sim in &self.sims {
+            let sub = sim.last();
+            let violations = sim.violations.map_or(String::from("-"), |v| v.to_string());
+            writeln!(
+                f,
+                "{:<20} {:>8} {:>10} {:>8} {:>14} {:>10}",
+                sim.name,
+                sub.x,
+                sub.depth,
+                sub.aim,
+                i64::from(sub.x) * i64::from(sub.depth),
+                violations
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fleet() {
+        let routes = vec![
+            (String::from("sample"), vec![
+                Command::Forward(5),
+                Command::Down(5),
+                Command::Forward(8),
+                Command::Up(3),
+                Command::Down(8),
+                Command::Forward(2)
+            ]),
+            (String::from("shallow"), vec![Command::Forward(4), Command::Down(1), Command::Forward(10)]),
+        ];
+        let floor = vec![45; 20];
+        let fleet = run(&routes, Some(&floor));
+        assert_eq!(fleet.sims[0].last(), Submarine { x: 15, depth: 60, aim: 10 });
+        // the last leg goes through the floor at both x 14 and x 15
+        assert_eq!(fleet.sims[0].violations, Some(2));
+        assert_eq!(fleet.sims[1].last(), Submarine { x: 14, depth: 10, aim: 1 });
+        assert_eq!(fleet.sims[1].violations, Some(0));
+
+        // after step 1 the subs are at (5, 0) and (4, 0), after step 2 both are unchanged
+        let encounters = fleet.proximity(1.0);
+        assert_eq!(encounters, vec![
+            Encounter { step: 1, a: 0, b: 1, distance: 1.0 },
+            Encounter { step: 2, a: 0, b: 1, distance: 1.0 },
+        ]);
+    }
+}
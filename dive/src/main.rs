@@ -1,6 +1,100 @@
+use std::fmt;
+use std::str::FromStr;
+
+mod checkpoint;
+mod collision;
+mod cost;
+mod fleet;
+mod normalise;
+mod repair;
+mod repl;
+
 fn main() {
-    let position = position(&get_movements("input".into()));
-    println!("{}", position);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("collision") => {
+            let steps = get_movements(args.get(1).expect("missing command file").clone());
+            let floor = sonar::get_depths(args.get(2).expect("missing floor file").clone());
+            print!("{}", collision::collisions(&commands(&steps), &floor));
+        }
+        Some("cost") => {
+            let steps = get_movements(args.get(1).expect("missing command file").clone());
+            let model = match args.get(2) {
+                Some(filename) => cost::CostModel::from_lines(&get_movements(filename.clone())),
+                None => cost::CostModel::default(),
+            };
+            print!("{}", cost::route(&commands(&steps), &model));
+        }
+        Some("fleet") => {
+            let mut floor = None;
+            let mut within = None;
+            let mut routes = vec![];
+            let mut rest = args[1..].iter();
+            while let Some(arg) = rest.next() {
+                match arg.as_str() {
+                    "--floor" => floor = Some(sonar::get_depths(rest.next().expect("missing floor file").clone())),
+                    "--proximity" => within = Some(rest.next().expect("missing distance").parse::<f64>().expect("invalid distance")),
+                    filename => routes.push((filename.to_string(), commands(&get_movements(filename.into())))),
+                }
+            }
+            let fleet = fleet::run(&routes, floor.as_deref());
+            print!("{}", fleet);
+            if let Some(within) = within {
+                for e in fleet.proximity(within) {
+                    println!("step {} {} and {} within {:.2}", e.step, fleet.sims[e.a].name, fleet.sims[e.b].name, e.distance);
+                }
+            }
+        }
+        Some("repl") => {
+            let stdin = std::io::stdin();
+            repl::run(stdin.lock(), std::io::stdout()).expect("failed to write output");
+        }
+        Some("normalise") => {
+            let commands = commands(&get_movements(args.get(1).expect("missing command file").clone()));
+            let normalised = normalise::normalise(&commands);
+            for command in &normalised {
+                println!("{}", command);
+            }
+            eprintln!(
+                "{} commands -> {} commands, equivalent: {}",
+                commands.len(),
+                normalised.len(),
+                normalise::equivalent(&commands, &normalised)
+            );
+        }
+        Some("repair") => {
+            let steps = get_movements(args.get(1).expect("missing command file").clone());
+            let target = args.get(2).expect("missing target product").parse::<i64>().expect("invalid target product");
+            // corrupted lines usually keep their scale, so only search distances already in the log
+            let max_distance = match args.get(3) {
+                Some(distance) => distance.parse::<i32>().expect("invalid distance"),
+                None => commands(&steps)
+                    .iter()
+                    .map(|command| match command {
+                        Command::Forward(distance) | Command::Up(distance) | Command::Down(distance) => distance.abs(),
+                    })
+                    .max()
+                    .unwrap_or(0),
+            };
+            // usually one bad line, so two at once is as far as it looks by default
+            let max_changes = args.get(4).map_or(2, |changes| changes.parse::<usize>().expect("invalid number of changes"));
+            print!("{}", repair::repair(&steps, target, max_distance, max_changes));
+        }
+        Some("position") | None => {
+            let model = match args.get(1) {
+                Some(filename) => cost::CostModel::from_lines(&get_movements(filename.clone())),
+                None => cost::CostModel::default(),
+            };
+            let route = position(&get_movements("input".into()), &model);
+            println!("position {}", route.position());
+            println!("fuel {}", route.fuel);
+            println!("time {}", route.time);
+        }
+        Some(command) => {
+            eprintln!("unknown command {}", command);
+            std::process::exit(1);
+        }
+    }
 }
 
 fn get_movements(filename: String) -> Vec<String> {
@@ -11,6 +105,58 @@ fn get_movements(filename: String) -> Vec<String> {
         .collect()
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Command {
+    Forward(i32),
+    Up(i32),
+    Down(i32),
+}
+
+impl Command {
+    fn parse(step: &str) -> Command {
+        step.parse::<Command>().unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(step: &str) -> Result<Command, String> {
+        let parts: Vec<_> = step.split_whitespace().collect();
+        let (direction, distance) = match parts[..] {
+            [direction, distance] => (direction, distance),
+            _ => return Err(format!("invalid step {}", step)),
+        };
+        let distance: i32 = distance.parse::<i32>().map_err(|_| String::from("invalid distance"))?;
+        match direction {
+            "forward" => Ok(Command::Forward(distance)),
+            "up" => Ok(Command::Up(distance)),
+            "down" => Ok(Command::Down(distance)),
+            _ => Err(format!("invalid direction {}", direction))
+        }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Command::Forward(distance) => write!(f, "forward {}", distance),
+            Command::Up(distance) => write!(f, "up {}", distance),
+            Command::Down(distance) => write!(f, "down {}", distance),
+        }
+    }
+}
+
+// checkpoint lines are skipped, only position checks them
+fn commands(steps: &[String]) -> Vec<Command> {
+    steps
+        .iter()
+        .filter(|step| !checkpoint::is_checkpoint(step))
+        .map(|step| Command::parse(step))
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 struct Submarine {
     x: i32,
     depth: i32,
@@ -30,30 +176,43 @@ impl Submarine {
     fn down(&mut self, distance: i32) {
         self.aim += distance;
     }
+
+    fn apply(&mut self, command: Command) {
+        match command {
+            Command::Forward(distance) => self.forward(distance),
+            Command::Up(distance) => self.up(distance),
+            Command::Down(distance) => self.down(distance),
+        }
+    }
 }
 
-impl Default for Submarine {
-    fn default() -> Submarine {
-        Submarine { x: 0, depth: 0, aim: 0 }
+fn replay(commands: &[Command]) -> Submarine {
+    let mut sub = Submarine::default();
+    for command in commands {
+        sub.apply(*command);
     }
+    sub
 }
 
-fn position(steps: &Vec<String>) -> i32 {
-    let mut sub = Submarine {..Default::default()};
+// follows the steps checking each checkpoint, and costs every leg on the way
+fn position(steps: &[String], model: &cost::CostModel) -> cost::Route {
+    let mut route = cost::Route::default();
 
-    for step in steps {
-        let parts: Vec<_> = step.split_whitespace().collect();
-        let direction: String = parts[0].into();
-        let distance: i32 = String::from(parts[1]).parse::<i32>().expect("invalid distance");
-        match direction.as_str() {
-            "forward" => sub.forward(distance),
-            "up" => sub.up(distance),
-            "down" => sub.down(distance),
-            _ => panic!("invalid direction {}", direction)
+    for (i, step) in steps.iter().enumerate() {
+        if checkpoint::is_checkpoint(step) {
+            let checkpoint = step.parse::<checkpoint::Checkpoint>().unwrap_or_else(|e| panic!("{}", e));
+            if let Err(diff) = checkpoint.check(&route.sub) {
+                panic!("checkpoint on line {} failed: {}{}", i + 1, step.trim(), diff);
+            }
+            continue;
         }
-        println!("x {} depth {} aim {}", sub.x, sub.depth, sub.aim);
+        let leg = model.leg(Command::parse(step), &route.sub);
+        let (fuel, time) = (leg.fuel, leg.time);
+        route.push(leg);
+        let sub = &route.sub;
+        println!("x {} depth {} aim {} fuel {} time {}", sub.x, sub.depth, sub.aim, fuel, time);
     }
-    sub.x * sub.depth
+    route
 }
 
 /*
@@ -63,6 +222,7 @@ forward 8
 up 3
 down 8
 forward 2
+expect x=15 depth=60 aim=10
 */
 
 #[cfg(test)]
@@ -80,6 +240,39 @@ mod tests {
             String::from("down 8"),
             String::from("forward 2")
         ];
-        assert_eq!(position(&steps), 900);
+        let route = position(&steps, &cost::CostModel::default());
+        assert_eq!(route.position(), 900);
+        // the default model charges one unit per unit of distance
+        assert_eq!((route.fuel, route.time), (31, 31));
+        assert_eq!(route.legs.iter().map(|leg| leg.fuel).collect::<Vec<_>>(), vec![5, 5, 8, 3, 8, 2]);
+    }
+
+    #[test]
+    fn test_checkpoints() {
+        let steps = vec![
+            String::from("forward 5"),
+            String::from("down 5"),
+            String::from("forward 8"),
+            String::from("expect x=13 depth=40"),
+            String::from("up 3"),
+            String::from("down 8"),
+            String::from("forward 2"),
+            String::from("expect x=15 depth=60 aim=10")
+        ];
+        assert_eq!(position(&steps, &cost::CostModel::default()).position(), 900);
+        assert_eq!(commands(&steps).len(), 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "checkpoint on line 4 failed: expect x=13 depth=45")]
+    fn test_failed_checkpoint() {
+        let steps = vec![
+            String::from("forward 5"),
+            String::from("down 5"),
+            String::from("forward 8"),
+            String::from("expect x=13 depth=45"),
+            String::from("forward 2")
+        ];
+        position(&steps, &cost::CostModel::default());
     }
 }
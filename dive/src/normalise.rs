@@ -0,0 +1,105 @@
+use crate::{replay, Command};
+
+/*
+shortest command list that ends in the same state:
+- zero distance commands are dropped
+- runs of up/down fold into one net aim change
+- runs of forward fold into one, which is safe because aim can't change
+  between them
+- aim changes are written with a positive distance, so down -2 becomes up 2
+folding is repeated, so forward 2, up 1, down 1, forward 3 ends up as forward 5
+*/
+pub fn normalise(commands: &[Command]) -> Vec<Command> {
+    let mut normalised: Vec<Command> = vec![];
+    for command in commands {
+        let mut next = canonical(*command);
+        while let Some(merged) = normalised.last().and_then(|last| merge(*last, next)) {
+            normalised.pop();
+            next = merged;
+        }
+        if distance(next) != 0 {
+            normalised.push(next);
+        }
+    }
+    normalised
+}
+
+pub fn equivalent(a: &[Command], b: &[Command]) -> bool {
+    replay(a) == replay(b)
+}
+
+fn distance(command: Command) -> i32 {
+    match command {
+        Command::Forward(distance) | Command::Up(distance) | Command::Down(distance) => distance,
+    }
+}
+
+// signed change in aim, None for forward
+fn aim(command: Command) -> Option<i32> {
+    match command {
+        Command::Forward(_) => None,
+        Command::Up(distance) => distance.checked_neg(),
+        Command::Down(distance) => Some(distance),
+    }
+}
+
+fn from_aim(aim: i32) -> Option<Command> {
+    if aim >= 0 {
+        Some(Command::Down(aim))
+    } else {
+        aim.checked_neg().map(Command::Up)
+    }
+}
+
+fn canonical(command: Command) -> Command {
+    aim(command).and_then(from_aim).unwrap_or(command)
+}
+
+// None when the commands can't be folded, or folding would overflow
+fn merge(a: Command, b: Command) -> Option<Command> {
+    if let (Command::Forward(a), Command::Forward(b)) = (a, b) {
+        return a.checked_add(b).map(Command::Forward);
+    }
+    from_aim(aim(a)?.checked_add(aim(b)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalise() {
+        let commands = vec![
+            Command::Forward(5),
+            Command::Down(5),
+            Command::Down(0),
+            Command::Forward(8),
+            Command::Up(3),
+            Command::Down(8),
+            Command::Forward(2),
+            Command::Up(1),
+            Command::Down(1),
+            Command::Forward(0),
+            Command::Forward(4),
+            Command::Up(7)
+        ];
+        let normalised = normalise(&commands);
+        assert_eq!(normalised, vec![
+            Command::Forward(5),
+            Command::Down(5),
+            Command::Forward(8),
+            Command::Down(5),
+            Command::Forward(6),
+            Command::Up(7)
+        ]);
+        assert!(equivalent(&commands, &normalised));
+        assert!(!equivalent(&commands, &normalised[1..]));
+    }
+
+    #[test]
+    fn test_normalise_cancels_out() {
+        let commands = vec![Command::Down(3), Command::Up(3), Command::Forward(0)];
+        assert_eq!(normalise(&commands), vec![]);
+        assert_eq!(normalise(&[Command::Down(-2)]), vec![Command::Up(2)]);
+    }
+}
@@ -0,0 +1,198 @@
+use std::fmt;
+
+use crate::{checkpoint, Command};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Change {
+    // line in the command file, counting from 1
+    pub line: usize,
+    pub original: Command,
+    pub replacement: Command,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Repair {
+    // every way of changing as few commands as will do that ends on target, in line order.
+    // one fix with no changes when the route already matches, none when nothing within max_changes does
+    pub fixes: Vec<Vec<Change>>,
+    pub max_changes: usize,
+}
+
+// x, depth and aim widened so products of long routes don't overflow
+#[derive(Clone, Copy, Default)]
+struct State {
+    x: i64,
+    depth: i64,
+    aim: i64,
+}
+
+impl State {
+    fn apply(mut self, command: Command) -> State {
+        match command {
+            Command::Forward(distance) => {
+                self.x += i64::from(distance);
+                self.depth += self.aim * i64::from(distance);
+            }
+            Command::Up(distance) => self.aim -= i64::from(distance),
+            Command::Down(distance) => self.aim += i64::from(distance),
+        }
+        self
+    }
+}
+
+/*
+what the commands after a point do to whatever state they start from:
+x grows by forward, depth by aim * forward + depth
+*/
+#[derive(Clone, Copy, Default)]
+struct Suffix {
+    forward: i64,
+    depth: i64,
+}
+
+impl Suffix {
+    fn prepend(self, command: Command) -> Suffix {
+        match command {
+            Command::Forward(distance) => Suffix { forward: self.forward + i64::from(distance), ..self },
+            Command::Up(distance) => Suffix { depth: self.depth - i64::from(distance) * self.forward, ..self },
+            Command::Down(distance) => Suffix { depth: self.depth + i64::from(distance) * self.forward, ..self },
+        }
+    }
+
+    fn product(&self, state: State) -> i64 {
+        (state.x + self.forward) * (state.depth + state.aim * self.forward + self.depth)
+    }
+}
+
+/*
+tries swapping each command for every direction and every distance up to
+max_distance, looking for the ones that make the route end on target. when
+no single command does, it goes on to every pair of commands and so on up
+to max_changes, stopping at the first number of changes that works. the
+state is carried forward a command at a time and the effect of the rest of
+the route is worked out once per command, so each candidate costs the same
+however long the log is, but each extra change multiplies the candidates.
+*/
+pub fn repair(steps: &[String], target: i64, max_distance: i32, max_changes: usize) -> Repair {
+    let moves: Vec<(usize, Command)> = steps
+        .iter()
+        .enumerate()
+        .filter(|(_, step)| !checkpoint::is_checkpoint(step))
+        .map(|(i, step)| (i + 1, Command::parse(step)))
+        .collect();
+
+    let mut after = vec![Suffix::default(); moves.len() + 1];
+    for i in (0..moves.len()).rev() {
+        after[i] = after[i + 1].prepend(moves[i].1);
+    }
+    let mut repair = Repair { fixes: vec![], max_changes };
+    if after[0].product(State::default()) == target {
+        repair.fixes.push(vec![]);
+        return repair;
+    }
+    // up 0 and down 0 are the same no-op as forward 0
+    let replacements: Vec<Command> = (0..=max_distance)
+        .flat_map(|distance| [Command::Forward(distance), Command::Up(distance), Command::Down(distance)])
+        .filter(|replacement| !matches!(replacement, Command::Up(0) | Command::Down(0)))
+        .collect();
+    let search = Search { moves: &moves, after: &after, replacements: &replacements, target };
+    for changes in 1..=max_changes {
+        search.from(0, State::default(), changes, &mut vec![], &mut repair.fixes);
+        if !repair.fixes.is_empty() {
+            break;
+        }
+    }
+    repair
+}
+
+struct Search<'a> {
+    moves: &'a [(usize, Command)],
+    after: &'a [Suffix],
+    replacements: &'a [Command],
+    target: i64,
+}
+
+impl Search<'_> {
+    // changes the next command at or after start, with state what the route is in before start
+    fn from(&self, start: usize, mut state: State, changes: usize, chosen: &mut Vec<Change>, fixes: &mut Vec<Vec<Change>>) {
+        for i in start..self.moves.len() {
+            let (line, original) = self.moves[i];
+            for &replacement in self.replacements.iter().filter(|replacement| **replacement != original) {
+                let changed = state.apply(replacement);
+                chosen.push(Change { line, original, replacement });
+                if changes > 1 {
+                    self.from(i + 1, changed, changes - 1, chosen, fixes);
+                } else if self.after[i + 1].product(changed) == self.target {
+                    fixes.push(chosen.clone());
+                }
+                chosen.pop();
+            }
+            state = state.apply(original);
+        }
+    }
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.fixes.first() {
+            None => return writeln!(f, "no fix changing up to {} commands", self.max_changes),
+            Some(fix) if fix.is_empty() => return writeln!(f, "route already matches"),
+            Some(fix) if fix.len() > 1 => writeln!(f, "no single command fix, {} have to change:", fix.len())?,
+            Some(_) => {}
+        }
+        for fix in &self.fixes {
+            let changes: Vec<_> = fix
+                .iter()
+                .map(|change| format!("line {}: {} -> {}", change.line, change.original, change.replacement))
+                .collect();
+            writeln!(f, "{}", changes.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn steps(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| String::from(*line)).collect()
+    }
+
+    // the commands with every change in fix made
+    fn fixed(steps: &[String], fix: &[Change]) -> Vec<String> {
+        let mut fixed = steps.to_vec();
+        for change in fix {
+            fixed[change.line - 1] = change.replacement.to_string();
+        }
+        fixed
+    }
+
+    #[test]
+    fn test_repair() {
+        // forward 8 garbled into forward 3
+        let steps = steps(&["forward 5", "down 5", "forward 3", "up 3", "down 8", "forward 2"]);
+        let repair = repair(&steps, 900, 9, 2);
+        assert!(repair.fixes.contains(&vec![Change { line: 3, original: Command::Forward(3), replacement: Command::Forward(8) }]));
+        for fix in &repair.fixes {
+            assert_eq!(fix.len(), 1);
+            let sub = crate::replay(&crate::commands(&fixed(&steps, fix)));
+            assert_eq!(sub.x * sub.depth, 900);
+        }
+        assert_eq!(super::repair(&steps, 350, 9, 2).fixes, vec![vec![]]);
+    }
+
+    #[test]
+    fn test_repair_pairs() {
+        let steps = steps(&["forward 1", "expect x=1", "forward 1"]);
+        // no one command gets deeper than aim 2 for a single step forward
+        let repair = repair(&steps, 4, 2, 2);
+        assert_eq!(repair.fixes, vec![vec![
+            Change { line: 1, original: Command::Forward(1), replacement: Command::Down(1) },
+            Change { line: 3, original: Command::Forward(1), replacement: Command::Forward(2) },
+        ]]);
+        let sub = crate::replay(&crate::commands(&fixed(&steps, &repair.fixes[0])));
+        assert_eq!(sub.x * sub.depth, 4);
+        assert!(super::repair(&steps, 4, 2, 1).fixes.is_empty());
+    }
+}
@@ -0,0 +1,107 @@
+use std::io::{self, BufRead, Write};
+
+use crate::{Command, Submarine};
+
+/*
+reads one command per line and prints the sub after each, alongside the
+meta-commands:
+reset        start again from the surface
+undo         drop the last accepted command
+state        print the sub without moving it
+save <file>  write the accepted commands out in the input file format
+*/
+#[derive(Debug, Default)]
+pub struct Repl {
+    history: Vec<Command>,
+    sub: Submarine,
+}
+
+impl Repl {
+    pub fn handle(&mut self, line: &str) -> Result<String, String> {
+        let line = line.trim();
+        match line.split_whitespace().collect::<Vec<_>>()[..] {
+            ["reset"] => {
+                self.history.clear();
+                self.sub = Submarine::default();
+            }
+            ["undo"] => {
+                if self.history.pop().is_none() {
+                    return Err(String::from("nothing to undo"));
+                }
+                self.sub = Submarine::default();
+                for command in &self.history {
+                    self.sub.apply(*command);
+                }
+            }
+            ["state"] => {}
+            ["save", filename] => {
+                self.save(filename).map_err(|e| format!("could not save {}: {}", filename, e))?;
+                return Ok(format!("saved {} commands to {}", self.history.len(), filename));
+            }
+            _ => {
+                let command = line.parse::<Command>()?;
+                self.sub.apply(command);
+                self.history.push(command);
+            }
+        }
+        Ok(self.state())
+    }
+
+    fn state(&self) -> String {
+        format!("x {} depth {} aim {}", self.sub.x, self.sub.depth, self.sub.aim)
+    }
+
+    fn save(&self, filename: &str) -> io::Result<()> {
+        let mut file = std::fs::File::create(filename)?;
+        for command in &self.history {
+            writeln!(file, "{}", command)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn run<R: BufRead, W: Write>(input: R, mut output: W) -> io::Result<()> {
+    let mut repl = Repl::default();
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match repl.handle(&line) {
+            Ok(message) => writeln!(output, "{}", message)?,
+            Err(message) => writeln!(output, "error: {}", message)?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repl() {
+        let saved = std::env::temp_dir().join("dive_repl_test");
+        let input = format!(
+            "forward 5\ndown 5\nsideways 3\nforward 8\nundo\nstate\nforward 8\nsave {}\nreset\nundo\n",
+            saved.display()
+        );
+        let mut output = vec![];
+        run(input.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().collect::<Vec<_>>(), vec![
+            "x 5 depth 0 aim 0",
+            "x 5 depth 0 aim 5",
+            "error: invalid direction sideways",
+            "x 13 depth 40 aim 5",
+            "x 5 depth 0 aim 5",
+            "x 5 depth 0 aim 5",
+            "x 13 depth 40 aim 5",
+            &format!("saved 3 commands to {}", saved.display()),
+            "x 0 depth 0 aim 0",
+            "error: nothing to undo",
+        ]);
+        assert_eq!(std::fs::read_to_string(&saved).unwrap(), "forward 5\ndown 5\nforward 8\n");
+        std::fs::remove_file(saved).unwrap();
+    }
+}